@@ -25,12 +25,22 @@ pub struct DumbBuffer<'a> {
 impl<'a> DumbBuffer<'a> {
     /// Attempts to create a `DumbBuffer` from the given size and bits per
     /// pixel.
+    ///
+    /// # Errors
+    ///
+    /// `ErrorKind::ResourceExhausted` - Returned if the device has run out of
+    /// memory for buffers, which usually means they are being leaked.
     pub fn create<T: 'a + AsRef<File>>(device: &'a T, width: u32, height: u32, bpp: u8) -> Result<DumbBuffer> {
-        let raw = try!(ffi::DrmModeCreateDumbBuffer::new(device.as_ref().as_raw_fd(), width, height, bpp));
+        let raw = try!(ffi::DrmModeCreateDumbBuffer::new(device.as_ref().as_raw_fd(), width, height, bpp)
+                       .map_err(super::exhausted));
         let buffer = DumbBuffer {
             device: device.as_ref(),
             size: (width, height),
-            depth: 24,
+            depth: match bpp {
+                8 => 8,
+                16 => 16,
+                _ => 24
+            },
             bpp: bpp,
             pitch: raw.raw.pitch,
             handle: raw.raw.handle,
@@ -53,7 +63,10 @@ impl<'a> DumbBuffer<'a> {
         };
         let mapping = DumbMapping {
             buffer: PhantomData,
-            map: map
+            map: map,
+            size: self.size,
+            bpp: self.bpp,
+            pitch: self.pitch
         };
         Ok(mapping)
     }
@@ -71,7 +84,46 @@ impl<'a> Drop for DumbBuffer<'a> {
 /// is copied to the graphics card on each write.
 pub struct DumbMapping<'a> {
     pub map: &'a mut [u8],
-    buffer: PhantomData<DumbBuffer<'a>>
+    buffer: PhantomData<DumbBuffer<'a>>,
+    size: (u32, u32),
+    bpp: u8,
+    pitch: u32
+}
+
+impl<'a> DumbMapping<'a> {
+    /// Fill the entire buffer with a single packed pixel value.
+    ///
+    /// The value is written in little-endian byte order, which is the layout
+    /// used by DRM pixel formats. For a 32 bpp `XRGB8888` buffer, `0x00ff0000`
+    /// is red.
+    pub fn fill(&mut self, color: u32) {
+        let (width, height) = self.size;
+        self.fill_rect(0, 0, width, height, color);
+    }
+
+    /// Fill a rectangle of the buffer with a single packed pixel value. The
+    /// rectangle is clipped to the size of the buffer.
+    pub fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, color: u32) {
+        let bytes = ((self.bpp as usize) + 7) / 8;
+        if bytes == 0 {
+            return;
+        }
+        let pixel: Vec<u8> = (0..bytes).map(| i | (color >> (8 * i)) as u8).collect();
+        let (buf_width, buf_height) = self.size;
+        let x_end = x.saturating_add(width).min(buf_width) as usize;
+        let y_end = y.saturating_add(height).min(buf_height) as usize;
+
+        for row in (y as usize)..y_end {
+            let start = row * self.pitch as usize + x as usize * bytes;
+            let end = row * self.pitch as usize + x_end * bytes;
+            if start >= end {
+                continue;
+            }
+            for chunk in self.map[start..end].chunks_mut(bytes) {
+                chunk.copy_from_slice(&pixel);
+            }
+        }
+    }
 }
 
 impl<'a> Drop for DumbMapping<'a> {
@@ -25,7 +25,17 @@ const unsigned int FFI_DRM_MODE_FLAG_PIXMUX =       DRM_MODE_FLAG_PIXMUX;
 const unsigned int FFI_DRM_MODE_FLAG_DBLCLK =       DRM_MODE_FLAG_DBLCLK;
 const unsigned int FFI_DRM_MODE_FLAG_CLKDIV2 =      DRM_MODE_FLAG_CLKDIV2;
 
-// TODO: Stereo modes here
+// Stereo modes
+const unsigned int FFI_DRM_MODE_FLAG_3D_MASK =      DRM_MODE_FLAG_3D_MASK;
+
+// Device capabilities
+const uint64_t FFI_DRM_CAP_CURSOR_WIDTH =           DRM_CAP_CURSOR_WIDTH;
+const uint64_t FFI_DRM_CAP_CURSOR_HEIGHT =          DRM_CAP_CURSOR_HEIGHT;
+const uint64_t FFI_DRM_CAP_ASYNC_PAGE_FLIP =        DRM_CAP_ASYNC_PAGE_FLIP;
+
+// Page flip flags
+const unsigned int FFI_DRM_MODE_PAGE_FLIP_EVENT =   DRM_MODE_PAGE_FLIP_EVENT;
+const unsigned int FFI_DRM_MODE_PAGE_FLIP_ASYNC =   DRM_MODE_PAGE_FLIP_ASYNC;
 
 typedef enum {
     FFI_DRM_MODE_ENCODER_NONE =      DRM_MODE_ENCODER_NONE,
@@ -58,6 +68,18 @@ typedef enum {
     FFI_DRM_MODE_CONNECTOR_DSI =         DRM_MODE_CONNECTOR_DSI
 } ConnectorInterface;
 
+typedef enum {
+    FFI_DRM_MODE_OBJECT_CRTC =      DRM_MODE_OBJECT_CRTC,
+    FFI_DRM_MODE_OBJECT_CONNECTOR = DRM_MODE_OBJECT_CONNECTOR,
+    FFI_DRM_MODE_OBJECT_ENCODER =   DRM_MODE_OBJECT_ENCODER,
+    FFI_DRM_MODE_OBJECT_MODE =      DRM_MODE_OBJECT_MODE,
+    FFI_DRM_MODE_OBJECT_PROPERTY =  DRM_MODE_OBJECT_PROPERTY,
+    FFI_DRM_MODE_OBJECT_FB =        DRM_MODE_OBJECT_FB,
+    FFI_DRM_MODE_OBJECT_BLOB =      DRM_MODE_OBJECT_BLOB,
+    FFI_DRM_MODE_OBJECT_PLANE =     DRM_MODE_OBJECT_PLANE,
+    FFI_DRM_MODE_OBJECT_ANY =       DRM_MODE_OBJECT_ANY
+} ObjectType;
+
 typedef enum {
     FFI_DRM_MODE_CONNECTED      = 1,
     FFI_DRM_MODE_DISCONNECTED   = 2,
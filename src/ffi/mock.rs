@@ -0,0 +1,180 @@
+// Answers ioctls from the current thread's `MockCard` instead of the kernel.
+// Buffers are filled the way the kernel fills them: an array is only copied
+// out if the caller made room for all of it, and the count is always set to
+// the real size.
+
+use super::*;
+use ::mock::MockCard;
+use ::mode::Mode;
+
+use std::cell::RefCell;
+use std::ptr::copy_nonoverlapping;
+use libc::{self, c_char};
+
+type MockResult = ::std::result::Result<(), i32>;
+
+thread_local!(static CARD: RefCell<MockCard> = RefCell::new(MockCard::sample()));
+
+pub fn install(card: MockCard) {
+    CARD.with(| c | *c.borrow_mut() = card);
+}
+
+// Dispatch an ioctl by the name of its request code, returning the errno to
+// fail with. Requests the mock does not know about fail with ENOTTY, as an
+// unknown ioctl would.
+pub unsafe fn ioctl(code: &str, arg: *mut c_void) -> MockResult {
+    CARD.with(| card | {
+        let mut card = card.borrow_mut();
+        match code.trim_left_matches("FFI_DRM_IOCTL_") {
+            "SET_MASTER" | "DROP_MASTER" => Ok(()),
+            "GET_CAP" => get_cap(&card, &mut *(arg as *mut drm_get_cap)),
+            "MODE_GETRESOURCES" => get_resources(&card, &mut *(arg as *mut drm_mode_card_res)),
+            "MODE_GETCONNECTOR" => get_connector(&card, &mut *(arg as *mut drm_mode_get_connector)),
+            "MODE_GETENCODER" => get_encoder(&card, &mut *(arg as *mut drm_mode_get_encoder)),
+            "MODE_GETCRTC" => get_crtc(&card, &mut *(arg as *mut drm_mode_crtc)),
+            "MODE_GETPROPERTY" => get_property(&card, &mut *(arg as *mut drm_mode_get_property)),
+            "MODE_SETPROPERTY" => {
+                let raw = &*(arg as *const drm_mode_connector_set_property);
+                set_property(&mut card, raw.connector_id, raw.prop_id, raw.value)
+            },
+            "MODE_OBJ_SETPROPERTY" => {
+                let raw = &*(arg as *const drm_mode_obj_set_property);
+                set_property(&mut card, raw.obj_id, raw.prop_id, raw.value)
+            },
+            _ => Err(libc::ENOTTY)
+        }
+    })
+}
+
+// Copy `items` to the user buffer at `ptr` if it can hold them all, and
+// return the number of items available.
+unsafe fn copy_out<T: Copy>(ptr: u64, count: u32, items: &[T]) -> u32 {
+    if ptr != 0 && count as usize >= items.len() {
+        copy_nonoverlapping(items.as_ptr(), ptr as *mut T, items.len());
+    }
+    items.len() as u32
+}
+
+fn copy_name(name: &str, out: &mut [c_char; 32]) {
+    *out = [0; 32];
+    for (dst, src) in out.iter_mut().zip(name.bytes().take(31)) {
+        *dst = src as c_char;
+    }
+}
+
+fn modeinfo(mode: &Mode) -> drm_mode_modeinfo {
+    let mut raw: drm_mode_modeinfo = mode.clone().into();
+    copy_name(&mode.name, &mut raw.name);
+    raw
+}
+
+fn get_cap(card: &MockCard, raw: &mut drm_get_cap) -> MockResult {
+    match card.capabilities.iter().find(| &&(cap, _) | cap == raw.capability) {
+        Some(&(_, value)) => {
+            raw.value = value;
+            Ok(())
+        },
+        None => Err(libc::EINVAL)
+    }
+}
+
+unsafe fn get_resources(card: &MockCard, raw: &mut drm_mode_card_res) -> MockResult {
+    let connectors: Vec<u32> = card.connectors.iter().map(| c | c.id).collect();
+    let encoders: Vec<u32> = card.encoders.iter().map(| e | e.id).collect();
+    let crtcs: Vec<u32> = card.controllers.iter().map(| c | c.id).collect();
+    let fbs: Vec<u32> = Vec::new();
+
+    raw.count_connectors = copy_out(raw.connector_id_ptr, raw.count_connectors, &connectors);
+    raw.count_encoders = copy_out(raw.encoder_id_ptr, raw.count_encoders, &encoders);
+    raw.count_crtcs = copy_out(raw.crtc_id_ptr, raw.count_crtcs, &crtcs);
+    raw.count_fbs = copy_out(raw.fb_id_ptr, raw.count_fbs, &fbs);
+    raw.min_width = card.width_limits.0;
+    raw.max_width = card.width_limits.1;
+    raw.min_height = card.height_limits.0;
+    raw.max_height = card.height_limits.1;
+    Ok(())
+}
+
+unsafe fn get_connector(card: &MockCard, raw: &mut drm_mode_get_connector) -> MockResult {
+    let con = match card.connectors.iter().find(| c | c.id == raw.connector_id) {
+        Some(con) => con,
+        None => return Err(libc::ENOENT)
+    };
+    let modes: Vec<drm_mode_modeinfo> = con.modes.iter().map(modeinfo).collect();
+    let props: Vec<u32> = con.properties.iter().map(| &(id, _) | id).collect();
+    let values: Vec<u64> = con.properties.iter().map(| &(_, value) | value).collect();
+
+    raw.count_encoders = copy_out(raw.encoders_ptr, raw.count_encoders, &con.encoders);
+    raw.count_modes = copy_out(raw.modes_ptr, raw.count_modes, &modes);
+    copy_out(raw.prop_values_ptr, raw.count_props, &values);
+    raw.count_props = copy_out(raw.props_ptr, raw.count_props, &props);
+    raw.encoder_id = con.encoder;
+    raw.connector_type = con.interface as u32;
+    raw.connector_type_id = con.interface_id;
+    raw.connection = con.state as u32;
+    raw.mm_width = con.size.0;
+    raw.mm_height = con.size.1;
+    Ok(())
+}
+
+fn get_encoder(card: &MockCard, raw: &mut drm_mode_get_encoder) -> MockResult {
+    let enc = match card.encoders.iter().find(| e | e.id == raw.encoder_id) {
+        Some(enc) => enc,
+        None => return Err(libc::ENOENT)
+    };
+    raw.crtc_id = enc.controller;
+    raw.possible_crtcs = enc.possible_controllers;
+    raw.possible_clones = 0;
+    Ok(())
+}
+
+fn get_crtc(card: &MockCard, raw: &mut drm_mode_crtc) -> MockResult {
+    let crtc = match card.controllers.iter().find(| c | c.id == raw.crtc_id) {
+        Some(crtc) => crtc,
+        None => return Err(libc::ENOENT)
+    };
+    raw.fb_id = crtc.framebuffer;
+    raw.x = crtc.position.0;
+    raw.y = crtc.position.1;
+    raw.gamma_size = crtc.gamma_size;
+    match crtc.mode {
+        Some(ref mode) => {
+            raw.mode_valid = 1;
+            raw.mode = modeinfo(mode);
+        },
+        None => raw.mode_valid = 0
+    }
+    Ok(())
+}
+
+unsafe fn get_property(card: &MockCard, raw: &mut drm_mode_get_property) -> MockResult {
+    let prop = match card.properties.iter().find(| p | p.id == raw.prop_id) {
+        Some(prop) => prop,
+        None => return Err(libc::ENOENT)
+    };
+    let enums: Vec<drm_mode_property_enum> = prop.enums.iter().map(| &(value, ref name) | {
+        let mut entry: drm_mode_property_enum = Default::default();
+        entry.value = value;
+        copy_name(name, &mut entry.name);
+        entry
+    }).collect();
+
+    copy_name(&prop.name, &mut raw.name);
+    raw.count_values = copy_out(raw.values_ptr, raw.count_values, &prop.values);
+    raw.count_enum_blobs = copy_out(raw.enum_blob_ptr, raw.count_enum_blobs, &enums);
+    Ok(())
+}
+
+fn set_property(card: &mut MockCard, obj_id: u32, prop_id: u32, value: u64) -> MockResult {
+    let con = match card.connectors.iter_mut().find(| c | c.id == obj_id) {
+        Some(con) => con,
+        None => return Err(libc::ENOENT)
+    };
+    match con.properties.iter_mut().find(| &&mut (id, _) | id == prop_id) {
+        Some(prop) => {
+            prop.1 = value;
+            Ok(())
+        },
+        None => Err(libc::EINVAL)
+    }
+}
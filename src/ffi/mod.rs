@@ -1,33 +1,74 @@
 #![allow(dead_code)]
 
 mod drm_shim;
+#[cfg(feature="mock")]
+pub mod mock;
 
 pub use self::drm_shim::*;
 use std::io::Error;
-use ::result::Result;
+use ::result::{Result, ErrorKind, ChainErr};
 use std::os::unix::io::RawFd;
-use std::ptr::null;
-use libc::{ioctl, c_void};
+use std::ptr::null_mut;
+use libc::c_void;
+#[cfg(not(feature="mock"))]
+use libc::ioctl;
+
+// This macro simply wraps the ioctl call to return errno on failure, tagged
+// with the name of the ioctl that failed
+#[cfg(not(feature="mock"))]
+macro_rules! ioctl {
+    ( $fd:expr, $code:expr, $obj:expr ) => ( unsafe {
+        if ioctl($fd, $code, $obj as *mut _) != 0 {
+            return ioctl_error(stringify!($code), Error::last_os_error());
+        }
+    })
+}
 
-// This macro simply wraps the ioctl call to return errno on failure
+// With the mock feature the call never reaches the kernel, and is answered
+// from the mock card of the current thread instead
+#[cfg(feature="mock")]
 macro_rules! ioctl {
     ( $fd:expr, $code:expr, $obj:expr ) => ( unsafe {
-        if ioctl($fd, $code, $obj) != 0 {
-            return Err(Error::last_os_error().into());
+        let _ = $fd;
+        if let Err(errno) = mock::ioctl(stringify!($code), $obj as *mut _ as *mut c_void) {
+            return ioctl_error(stringify!($code), Error::from_raw_os_error(errno));
         }
     })
 }
 
+// Build an error from the errno of a failed ioctl, naming the operation that
+// failed. The underlying `io::Error` is kept as the cause.
+fn ioctl_error<T>(code: &'static str, err: Error) -> Result<T> {
+    let errno = err.raw_os_error().unwrap_or(0);
+    let op = code.trim_left_matches("FFI_DRM_IOCTL_");
+    Err(err).chain_err(|| ErrorKind::Ioctl(op, errno))
+}
+
 pub fn set_master(fd: RawFd) -> Result<()> {
-    ioctl!(fd, FFI_DRM_IOCTL_SET_MASTER, null() as *const c_void);
+    ioctl!(fd, FFI_DRM_IOCTL_SET_MASTER, null_mut::<c_void>());
     Ok(())
 }
 
 pub fn drop_master(fd: RawFd) -> Result<()> {
-    ioctl!(fd, FFI_DRM_IOCTL_DROP_MASTER, null() as *const c_void);
+    ioctl!(fd, FFI_DRM_IOCTL_DROP_MASTER, null_mut::<c_void>());
     Ok(())
 }
 
+#[derive(Debug)]
+pub struct DrmGetCap {
+    pub raw: drm_get_cap
+}
+
+impl DrmGetCap {
+    pub fn new(fd: RawFd, capability: u64) -> Result<DrmGetCap> {
+        let mut raw: drm_get_cap = Default::default();
+        raw.capability = capability;
+        ioctl!(fd, FFI_DRM_IOCTL_GET_CAP, &mut raw);
+        let cap = DrmGetCap { raw: raw };
+        Ok(cap)
+    }
+}
+
 #[derive(Debug)]
 pub struct DrmModeCardRes {
     pub raw: drm_mode_card_res,
@@ -39,38 +80,61 @@ pub struct DrmModeCardRes {
 
 impl DrmModeCardRes {
     pub fn new(fd: RawFd) -> Result<DrmModeCardRes> {
-        // Call ioctl to get the initial structure and buffer sizes
-        let mut raw: drm_mode_card_res = Default::default();
-        ioctl!(fd, FFI_DRM_IOCTL_MODE_GETRESOURCES, &raw);
-
-        // Create buffers for each array
-        let mut connectors: Vec<u32> =
-            vec![Default::default(); raw.count_connectors as usize];
-        let mut encoders: Vec<u32> =
-            vec![Default::default(); raw.count_encoders as usize];
-        let mut crtcs: Vec<u32> =
-            vec![Default::default(); raw.count_crtcs as usize];
-        let mut framebuffers: Vec<u32> =
-            vec![Default::default(); raw.count_fbs as usize];
-
-        // Pass a handle to the buffers to the raw struct
-        raw.connector_id_ptr = connectors.as_mut_slice().as_mut_ptr() as u64;
-        raw.encoder_id_ptr = encoders.as_mut_slice().as_mut_ptr() as u64;
-        raw.crtc_id_ptr = crtcs.as_mut_slice().as_mut_ptr() as u64;
-        raw.fb_id_ptr = framebuffers.as_mut_slice().as_mut_ptr() as u64;
-
-        // Call the ioctl again to fill up the structs
-        ioctl!(fd, FFI_DRM_IOCTL_MODE_GETRESOURCES, &raw);
-
-        let res = DrmModeCardRes{
-            raw: raw,
-            connectors: connectors,
-            encoders: encoders,
-            crtcs: crtcs,
-            framebuffers: framebuffers
-        };
-
-        Ok(res)
+        loop {
+            // Call ioctl to get the initial structure and buffer sizes
+            let mut raw: drm_mode_card_res = Default::default();
+            ioctl!(fd, FFI_DRM_IOCTL_MODE_GETRESOURCES, &mut raw);
+
+            let count_connectors = raw.count_connectors;
+            let count_encoders = raw.count_encoders;
+            let count_crtcs = raw.count_crtcs;
+            let count_fbs = raw.count_fbs;
+
+            // Create buffers for each array
+            let mut connectors: Vec<u32> =
+                vec![Default::default(); count_connectors as usize];
+            let mut encoders: Vec<u32> =
+                vec![Default::default(); count_encoders as usize];
+            let mut crtcs: Vec<u32> =
+                vec![Default::default(); count_crtcs as usize];
+            let mut framebuffers: Vec<u32> =
+                vec![Default::default(); count_fbs as usize];
+
+            // Pass a handle to the buffers to the raw struct
+            raw.connector_id_ptr = connectors.as_mut_slice().as_mut_ptr() as u64;
+            raw.encoder_id_ptr = encoders.as_mut_slice().as_mut_ptr() as u64;
+            raw.crtc_id_ptr = crtcs.as_mut_slice().as_mut_ptr() as u64;
+            raw.fb_id_ptr = framebuffers.as_mut_slice().as_mut_ptr() as u64;
+
+            // Call the ioctl again to fill up the structs
+            ioctl!(fd, FFI_DRM_IOCTL_MODE_GETRESOURCES, &mut raw);
+
+            // Connectors (such as DP-MST ports) and framebuffers can come and
+            // go between the two calls. If any count grew, the buffers were
+            // too small, so start over with fresh sizes.
+            if raw.count_connectors > count_connectors ||
+               raw.count_encoders > count_encoders ||
+               raw.count_crtcs > count_crtcs ||
+               raw.count_fbs > count_fbs {
+                continue;
+            }
+
+            // If any of them shrank, only the front of each buffer is valid
+            connectors.truncate(raw.count_connectors as usize);
+            encoders.truncate(raw.count_encoders as usize);
+            crtcs.truncate(raw.count_crtcs as usize);
+            framebuffers.truncate(raw.count_fbs as usize);
+
+            let res = DrmModeCardRes{
+                raw: raw,
+                connectors: connectors,
+                encoders: encoders,
+                crtcs: crtcs,
+                framebuffers: framebuffers
+            };
+
+            return Ok(res);
+        }
     }
 }
 
@@ -84,40 +148,85 @@ pub struct DrmModeGetConnector {
 }
 
 impl DrmModeGetConnector {
+    // Fetch the connector, having the kernel probe it for modes first. This
+    // can be slow, as it may read the EDID of the display.
     pub fn new(fd: RawFd, id: u32) -> Result<DrmModeGetConnector> {
-        // Call ioctl to get the initial structure and buffer sizes
-        let mut raw: drm_mode_get_connector = Default::default();
-        raw.connector_id = id;
-        ioctl!(fd, FFI_DRM_IOCTL_MODE_GETCONNECTOR, &raw);
-
-        // Create buffers for each array
-        let mut encoders: Vec<u32> =
-            vec![Default::default(); raw.count_encoders as usize];
-        let mut modes: Vec<drm_mode_modeinfo> =
-            vec![Default::default(); raw.count_modes as usize];
-        let mut properties: Vec<u32> =
-            vec![Default::default(); raw.count_props as usize];
-        let mut prop_values: Vec<u64> =
-            vec![Default::default(); raw.count_props as usize];
-
-        // Pass a handle to the buffers to the raw struct
-        raw.encoders_ptr = encoders.as_mut_slice().as_mut_ptr() as u64;
-        raw.modes_ptr = modes.as_mut_slice().as_mut_ptr() as u64;
-        raw.props_ptr = properties.as_mut_slice().as_mut_ptr() as u64;
-        raw.prop_values_ptr = prop_values.as_mut_slice().as_mut_ptr() as u64;
-
-        // Call the ioctl again to fill up the structs
-        ioctl!(fd, FFI_DRM_IOCTL_MODE_GETCONNECTOR, &raw);
+        Self::get(fd, id, true)
+    }
 
-        let conn = DrmModeGetConnector{
-            raw: raw,
-            encoders: encoders,
-            modes: modes,
-            properties: properties,
-            prop_values: prop_values
-        };
+    // Fetch the connector as the kernel currently knows it, without probing.
+    pub fn current(fd: RawFd, id: u32) -> Result<DrmModeGetConnector> {
+        Self::get(fd, id, false)
+    }
 
-        Ok(conn)
+    fn get(fd: RawFd, id: u32, probe: bool) -> Result<DrmModeGetConnector> {
+        // The kernel only probes when asked for zero modes, so point it at a
+        // single spare mode when a probe is not wanted
+        let mut stub: drm_mode_modeinfo = Default::default();
+        let stub_ptr = &mut stub as *mut drm_mode_modeinfo as u64;
+
+        loop {
+            // Call ioctl to get the initial structure and buffer sizes
+            let mut raw: drm_mode_get_connector = Default::default();
+            raw.connector_id = id;
+            if !probe {
+                raw.count_modes = 1;
+                raw.modes_ptr = stub_ptr;
+            }
+            ioctl!(fd, FFI_DRM_IOCTL_MODE_GETCONNECTOR, &mut raw);
+
+            let count_encoders = raw.count_encoders;
+            let count_modes = raw.count_modes;
+            let count_props = raw.count_props;
+
+            // Create buffers for each array
+            let mut encoders: Vec<u32> =
+                vec![Default::default(); count_encoders as usize];
+            let mut modes: Vec<drm_mode_modeinfo> =
+                vec![Default::default(); count_modes as usize];
+            let mut properties: Vec<u32> =
+                vec![Default::default(); count_props as usize];
+            let mut prop_values: Vec<u64> =
+                vec![Default::default(); count_props as usize];
+
+            // Pass a handle to the buffers to the raw struct
+            raw.encoders_ptr = encoders.as_mut_slice().as_mut_ptr() as u64;
+            raw.modes_ptr = modes.as_mut_slice().as_mut_ptr() as u64;
+            raw.props_ptr = properties.as_mut_slice().as_mut_ptr() as u64;
+            raw.prop_values_ptr = prop_values.as_mut_slice().as_mut_ptr() as u64;
+            if !probe && count_modes == 0 {
+                raw.count_modes = 1;
+                raw.modes_ptr = stub_ptr;
+            }
+
+            // Call the ioctl again to fill up the structs
+            ioctl!(fd, FFI_DRM_IOCTL_MODE_GETCONNECTOR, &mut raw);
+
+            // A hotplug between the two calls can change the counts. If any of
+            // them grew, the buffers were too small and nothing was filled in,
+            // so start over with fresh sizes.
+            if raw.count_encoders > count_encoders ||
+               raw.count_modes > count_modes ||
+               raw.count_props > count_props {
+                continue;
+            }
+
+            // If any of them shrank, only the front of each buffer is valid
+            encoders.truncate(raw.count_encoders as usize);
+            modes.truncate(raw.count_modes as usize);
+            properties.truncate(raw.count_props as usize);
+            prop_values.truncate(raw.count_props as usize);
+
+            let conn = DrmModeGetConnector{
+                raw: raw,
+                encoders: encoders,
+                modes: modes,
+                properties: properties,
+                prop_values: prop_values
+            };
+
+            return Ok(conn);
+        }
     }
 }
 
@@ -130,7 +239,7 @@ impl DrmModeGetEncoder {
     pub fn new(fd: RawFd, id: u32) -> Result<DrmModeGetEncoder> {
         let mut raw: drm_mode_get_encoder = Default::default();
         raw.encoder_id = id;
-        ioctl!(fd, FFI_DRM_IOCTL_MODE_GETENCODER, &raw);
+        ioctl!(fd, FFI_DRM_IOCTL_MODE_GETENCODER, &mut raw);
         let enc = DrmModeGetEncoder { raw: raw };
         Ok(enc)
     }
@@ -145,7 +254,7 @@ impl DrmModeGetCrtc {
     pub fn new(fd: RawFd, id: u32) -> Result<DrmModeGetCrtc> {
         let mut raw: drm_mode_crtc = Default::default();
         raw.crtc_id = id;
-        ioctl!(fd, FFI_DRM_IOCTL_MODE_GETCRTC, &raw);
+        ioctl!(fd, FFI_DRM_IOCTL_MODE_GETCRTC, &mut raw);
         let crtc = DrmModeGetCrtc { raw: raw };
         Ok(crtc)
     }
@@ -167,12 +276,114 @@ impl DrmModeSetCrtc {
         raw.mode_valid = 1;
         raw.count_connectors = connectors.len() as u32;
         raw.set_connectors_ptr = connectors.as_mut_slice().as_mut_ptr() as u64;
-        ioctl!(fd, FFI_DRM_IOCTL_MODE_SETCRTC, &raw);
+        ioctl!(fd, FFI_DRM_IOCTL_MODE_SETCRTC, &mut raw);
         let crtc = DrmModeSetCrtc { raw: raw };
         Ok(crtc)
     }
 }
 
+#[derive(Debug)]
+pub struct DrmModeGetProperty {
+    pub raw: drm_mode_get_property,
+    pub values: Vec<u64>,
+    pub enums: Vec<drm_mode_property_enum>
+}
+
+impl DrmModeGetProperty {
+    pub fn new(fd: RawFd, id: u32) -> Result<DrmModeGetProperty> {
+        loop {
+            // Call ioctl to get the initial structure and buffer sizes
+            let mut raw: drm_mode_get_property = Default::default();
+            raw.prop_id = id;
+            ioctl!(fd, FFI_DRM_IOCTL_MODE_GETPROPERTY, &mut raw);
+
+            let count_values = raw.count_values;
+            let count_enum_blobs = raw.count_enum_blobs;
+
+            // Create buffers for each array
+            let mut values: Vec<u64> =
+                vec![Default::default(); count_values as usize];
+            let mut enums: Vec<drm_mode_property_enum> =
+                vec![Default::default(); count_enum_blobs as usize];
+
+            // Pass a handle to the buffers to the raw struct
+            raw.values_ptr = values.as_mut_slice().as_mut_ptr() as u64;
+            raw.enum_blob_ptr = enums.as_mut_slice().as_mut_ptr() as u64;
+
+            // Call the ioctl again to fill up the structs
+            ioctl!(fd, FFI_DRM_IOCTL_MODE_GETPROPERTY, &mut raw);
+
+            if raw.count_values > count_values ||
+               raw.count_enum_blobs > count_enum_blobs {
+                continue;
+            }
+
+            values.truncate(raw.count_values as usize);
+            enums.truncate(raw.count_enum_blobs as usize);
+
+            let prop = DrmModeGetProperty {
+                raw: raw,
+                values: values,
+                enums: enums
+            };
+
+            return Ok(prop);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DrmModeConnectorSetProperty {
+    pub raw: drm_mode_connector_set_property
+}
+
+impl DrmModeConnectorSetProperty {
+    pub fn new(fd: RawFd, connector_id: u32, prop_id: u32, value: u64) -> Result<DrmModeConnectorSetProperty> {
+        let mut raw: drm_mode_connector_set_property = Default::default();
+        raw.connector_id = connector_id;
+        raw.prop_id = prop_id;
+        raw.value = value;
+        ioctl!(fd, FFI_DRM_IOCTL_MODE_SETPROPERTY, &mut raw);
+        let prop = DrmModeConnectorSetProperty { raw: raw };
+        Ok(prop)
+    }
+}
+
+#[derive(Debug)]
+pub struct DrmModeObjSetProperty {
+    pub raw: drm_mode_obj_set_property
+}
+
+impl DrmModeObjSetProperty {
+    pub fn new(fd: RawFd, obj_id: u32, obj_type: u32, prop_id: u32, value: u64) -> Result<DrmModeObjSetProperty> {
+        let mut raw: drm_mode_obj_set_property = Default::default();
+        raw.obj_id = obj_id;
+        raw.obj_type = obj_type;
+        raw.prop_id = prop_id;
+        raw.value = value;
+        ioctl!(fd, FFI_DRM_IOCTL_MODE_OBJ_SETPROPERTY, &mut raw);
+        let prop = DrmModeObjSetProperty { raw: raw };
+        Ok(prop)
+    }
+}
+
+#[derive(Debug)]
+pub struct DrmModePageFlip {
+    pub raw: drm_mode_crtc_page_flip
+}
+
+impl DrmModePageFlip {
+    pub fn new(fd: RawFd, crtc_id: u32, fb_id: u32, flags: u32) -> Result<DrmModePageFlip> {
+        let mut raw: drm_mode_crtc_page_flip = Default::default();
+        raw.crtc_id = crtc_id;
+        raw.fb_id = fb_id;
+        raw.flags = flags;
+        ioctl!(fd, FFI_DRM_IOCTL_MODE_PAGE_FLIP, &mut raw);
+        let flip = DrmModePageFlip { raw: raw };
+        Ok(flip)
+    }
+}
+
 #[derive(Debug)]
 pub struct DrmModeAddFb {
     pub raw: drm_mode_fb_cmd
@@ -188,19 +399,34 @@ impl DrmModeAddFb {
         raw.bpp = bpp as u32;
         raw.pitch = pitch;
         raw.handle = handle;
-        ioctl!(fd, FFI_DRM_IOCTL_MODE_ADDFB, &raw);
+        ioctl!(fd, FFI_DRM_IOCTL_MODE_ADDFB, &mut raw);
         let fb = DrmModeAddFb { raw: raw };
         Ok(fb)
     }
 }
 
+#[derive(Debug)]
+pub struct DrmModeGetFb {
+    pub raw: drm_mode_fb_cmd
+}
+
+impl DrmModeGetFb {
+    pub fn new(fd: RawFd, id: u32) -> Result<DrmModeGetFb> {
+        let mut raw: drm_mode_fb_cmd = Default::default();
+        raw.fb_id = id;
+        ioctl!(fd, FFI_DRM_IOCTL_MODE_GETFB, &mut raw);
+        let fb = DrmModeGetFb { raw: raw };
+        Ok(fb)
+    }
+}
+
 #[derive(Debug)]
 pub struct DrmModeRmFb;
 
 impl DrmModeRmFb {
     pub fn new(fd: RawFd, id: u32) -> Result<DrmModeRmFb> {
-        let raw = id;
-        ioctl!(fd, FFI_DRM_IOCTL_MODE_RMFB, &raw);
+        let mut raw = id;
+        ioctl!(fd, FFI_DRM_IOCTL_MODE_RMFB, &mut raw);
         let fb = DrmModeRmFb;
         Ok(fb)
     }
@@ -217,7 +443,7 @@ impl DrmModeCreateDumbBuffer {
         raw.width = width;
         raw.height = height;
         raw.bpp = bpp as u32;
-        ioctl!(fd, FFI_DRM_IOCTL_MODE_CREATE_DUMB, &raw);
+        ioctl!(fd, FFI_DRM_IOCTL_MODE_CREATE_DUMB, &mut raw);
         let buffer = DrmModeCreateDumbBuffer { raw: raw };
         Ok(buffer)
     }
@@ -232,7 +458,7 @@ impl DrmModeMapDumbBuffer {
     pub fn new(fd: RawFd, handle: u32) -> Result<DrmModeMapDumbBuffer> {
         let mut raw: drm_mode_map_dumb = Default::default();
         raw.handle = handle;
-        ioctl!(fd, FFI_DRM_IOCTL_MODE_MAP_DUMB, &raw);
+        ioctl!(fd, FFI_DRM_IOCTL_MODE_MAP_DUMB, &mut raw);
         let map = DrmModeMapDumbBuffer { raw: raw };
         Ok(map)
     }
@@ -247,7 +473,7 @@ impl DrmModeDestroyDumbBuffer {
     pub fn new(fd: RawFd, handle: u32) -> Result<DrmModeDestroyDumbBuffer> {
         let mut raw: drm_mode_destroy_dumb = Default::default();
         raw.handle = handle;
-        ioctl!(fd, FFI_DRM_IOCTL_MODE_MAP_DUMB, &raw);
+        ioctl!(fd, FFI_DRM_IOCTL_MODE_MAP_DUMB, &mut raw);
         let destroy = DrmModeDestroyDumbBuffer { raw: raw };
         Ok(destroy)
     }
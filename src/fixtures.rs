@@ -0,0 +1,24 @@
+//! Sample data shared by the unit tests and the mock device.
+
+use mode::Mode;
+use ffi;
+
+/// Build a mode with plausible timings whose refresh rate comes out as the
+/// one requested.
+pub fn mode(width: u16, height: u16, refresh: u32, preferred: bool) -> Mode {
+    let (htotal, vtotal) = (width + 280, height + 45);
+    Mode {
+        name: format!("{}x{}", width, height),
+        clock: htotal as u32 * vtotal as u32 * refresh / 1000,
+        display: (width, height),
+        hsync: (width + 88, width + 132),
+        vsync: (height + 4, height + 9),
+        hskew: 0,
+        vscan: 0,
+        htotal: htotal,
+        vtotal: vtotal,
+        vrefresh: refresh,
+        flags: 0,
+        mode_type: if preferred { unsafe { ffi::FFI_DRM_MODE_TYPE_PREFERRED } } else { 0 }
+    }
+}
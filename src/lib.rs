@@ -31,25 +31,65 @@ extern crate libc;
 
 mod ffi;
 pub mod result;
+pub mod mode;
 
 #[cfg(feature="dumbbuffer")]
 pub mod dumbbuffer;
 
+#[cfg(feature="mock")]
+pub mod mock;
+#[cfg(any(test, feature="mock"))]
+mod fixtures;
+
 use result::{Result, ErrorKind};
+pub use mode::{Mode, ModeFilter, ModeFlags, ModeTypeFlags};
 
-use std::os::unix::io::AsRawFd;
-use std::fs::{File, OpenOptions};
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::os::unix::fs::MetadataExt;
+use std::fs::{self, File, OpenOptions};
 use std::path::Path;
 use std::sync::{Mutex, MutexGuard};
 use std::mem::transmute;
-use std::vec::IntoIter;
+use std::io::{self, Error};
 use std::ffi::CStr;
+use std::time::Duration;
+use std::vec::IntoIter;
 
 pub type ResourceId = u32;
 pub type ConnectorId = ResourceId;
 pub type EncoderId = ResourceId;
 pub type ControllerId = ResourceId;
 pub type FramebufferId = ResourceId;
+pub type PropertyId = ResourceId;
+
+// Creating an object fails with ENOSPC or ENOMEM once the device runs out of
+// room for it, which is almost always caused by leaking earlier objects.
+fn exhausted(err: result::Error) -> result::Error {
+    match err {
+        result::Error(ErrorKind::Ioctl(op, errno), state) => {
+            if errno == libc::ENOSPC || errno == libc::ENOMEM {
+                result::Error(ErrorKind::ResourceExhausted(op, errno), state)
+            } else {
+                result::Error(ErrorKind::Ioctl(op, errno), state)
+            }
+        },
+        err => err
+    }
+}
+
+// Build a bus id such as `pci:0000:01:00.0` from a device's sysfs directory,
+// using the name of its bus subsystem and of the device itself.
+fn sysfs_bus_id<P: AsRef<Path>>(device: P) -> Result<String> {
+    let device = device.as_ref();
+    let subsystem = try!(fs::read_link(device.join("subsystem")));
+    let path = try!(fs::canonicalize(device));
+    match (subsystem.file_name(), path.file_name()) {
+        (Some(bus), Some(name)) => {
+            Ok(format!("{}:{}", bus.to_string_lossy(), name.to_string_lossy()))
+        },
+        _ => Err(Error::new(io::ErrorKind::NotFound, "malformed sysfs device path").into())
+    }
+}
 
 /// A `MasterLock` is a lock for a `MasterDevice`. It ensures that only one
 /// handle to the DRM Master is in use at once.
@@ -110,6 +150,26 @@ impl From<File> for Device {
     }
 }
 
+impl AsRawFd for Device {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+/// A `Device` can be created from a file descriptor that is already open, such
+/// as one handed out by a session manager.
+impl FromRawFd for Device {
+    unsafe fn from_raw_fd(fd: RawFd) -> Device {
+        Device::from(File::from_raw_fd(fd))
+    }
+}
+
+impl IntoRawFd for Device {
+    fn into_raw_fd(self) -> RawFd {
+        self.file.into_raw_fd()
+    }
+}
+
 impl<'a> Device {
     /// Attempt to open the file specified at the given path.
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
@@ -118,6 +178,96 @@ impl<'a> Device {
         Ok(dev)
     }
 
+    /// Attempt to open the card whose bus id matches the one given, such as
+    /// `pci:0000:01:00.0`. The `pci:` prefix may be omitted.
+    ///
+    /// Cards are matched through `/sys/class/drm`, so only the matching
+    /// `/dev/dri/card*` node is opened.
+    ///
+    /// # Errors
+    ///
+    /// `ErrorKind::DeviceNotFound` - Returned if no card has a matching bus id.
+    pub fn open_by_bus_id(bus_id: &str) -> Result<Self> {
+        let mut cards: Vec<String> = try!(fs::read_dir("/sys/class/drm"))
+            .filter_map(| entry | entry.ok())
+            .filter_map(| entry | entry.file_name().into_string().ok())
+            .filter(| name | {
+                name.len() > 4 && name.starts_with("card") &&
+                name[4..].chars().all(| c | c.is_digit(10))
+            })
+            .collect();
+        cards.sort();
+
+        for card in cards {
+            let unique = match sysfs_bus_id(format!("/sys/class/drm/{}/device", card)) {
+                Ok(unique) => unique,
+                Err(_) => continue
+            };
+            if unique == bus_id || unique.trim_left_matches("pci:") == bus_id {
+                return Self::open(format!("/dev/dri/{}", card));
+            }
+        }
+
+        Err(ErrorKind::DeviceNotFound(bus_id.to_string()).into())
+    }
+
+    /// Return the bus id of the device, such as `pci:0000:01:00.0`.
+    ///
+    /// The id is read from sysfs rather than with `DRM_IOCTL_GET_UNIQUE`,
+    /// which only reports it once a master has issued `SET_VERSION`.
+    pub fn bus_id(&self) -> Result<String> {
+        let rdev = try!(self.file.metadata()).rdev();
+        let major = ((rdev >> 8) & 0xfff) | ((rdev >> 32) & !0xfff);
+        let minor = (rdev & 0xff) | ((rdev >> 12) & !0xff);
+        sysfs_bus_id(format!("/sys/dev/char/{}:{}/device", major, minor))
+    }
+
+    /// Return the maximum width and height of the hardware cursor. Cursor
+    /// buffers should be allocated at this size, as many drivers reject any
+    /// other dimensions.
+    pub fn cursor_size(&self) -> Result<(u64, u64)> {
+        let fd = self.file.as_raw_fd();
+        let width = try!(ffi::DrmGetCap::new(fd, unsafe { ffi::FFI_DRM_CAP_CURSOR_WIDTH }));
+        let height = try!(ffi::DrmGetCap::new(fd, unsafe { ffi::FFI_DRM_CAP_CURSOR_HEIGHT }));
+        Ok((width.raw.value, height.raw.value))
+    }
+
+    /// Block until the device has events pending or the timeout expires. If no
+    /// timeout is given this will wait indefinitely. Timeouts are rounded up to
+    /// the next millisecond.
+    ///
+    /// Returns `true` if events are ready to be read and `false` if the call
+    /// timed out. An error is returned if the device reports an error or has
+    /// been hung up.
+    pub fn wait_readable(&self, timeout: Option<Duration>) -> Result<bool> {
+        let ms = match timeout {
+            Some(t) => {
+                let nanos = t.subsec_nanos() as u64;
+                let ms = t.as_secs().saturating_mul(1000)
+                    .saturating_add((nanos + 999_999) / 1_000_000);
+                if ms > i32::max_value() as u64 { i32::max_value() } else { ms as i32 }
+            },
+            None => -1
+        };
+        let mut fds = libc::pollfd {
+            fd: self.file.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0
+        };
+        match unsafe { libc::poll(&mut fds, 1, ms) } {
+            -1 => Err(Error::last_os_error().into()),
+            0 => Ok(false),
+            _ if fds.revents & libc::POLLIN != 0 => Ok(true),
+            _ if fds.revents & libc::POLLNVAL != 0 => {
+                Err(Error::from_raw_os_error(libc::EBADF).into())
+            },
+            _ if fds.revents & (libc::POLLERR | libc::POLLHUP) != 0 => {
+                Err(Error::new(io::ErrorKind::BrokenPipe, "device hung up or reported an error").into())
+            },
+            _ => Ok(false)
+        }
+    }
+
     /// Acquire the master lock and create a `MasterDevice`
     pub fn lock_master(&'a self) -> Result<MasterDevice<'a>> {
         let lock = try!(MasterLock::from_device(self));
@@ -130,6 +280,8 @@ impl<'a> Device {
             encoders: Mutex::new(raw.encoders.clone()),
             controllers: Mutex::new(raw.crtcs.clone()),
             controllers_order: raw.crtcs.clone(),
+            width_limits: (raw.raw.min_width, raw.raw.max_width),
+            height_limits: (raw.raw.min_height, raw.raw.max_height),
         };
         Ok(master)
     }
@@ -144,6 +296,8 @@ pub struct MasterDevice<'a> {
     encoders: Mutex<Vec<EncoderId>>,
     controllers: Mutex<Vec<ControllerId>>,
     controllers_order: Vec<ControllerId>,
+    width_limits: (u32, u32),
+    height_limits: (u32, u32),
 }
 
 impl<'a> AsRef<File> for MasterDevice<'a> {
@@ -155,10 +309,25 @@ impl<'a> AsRef<File> for MasterDevice<'a> {
 impl<'a> MasterDevice<'a> {
     /// Attempt to create an abstract `Framebuffer` object from the provided
     /// `Buffer`.
+    ///
+    /// # Errors
+    ///
+    /// `ErrorKind::InvalidFormat` - Returned if the buffer's bpp and depth do
+    /// not form a supported pair. The supported pairs are 8/8, 16/15, 16/16,
+    /// 24/24, 32/24, 32/30 and 32/32.
+    ///
+    /// `ErrorKind::ResourceExhausted` - Returned if the device has run out of
+    /// space for framebuffers, which usually means they are being leaked.
     pub fn framebuffer<T: Buffer>(&self, buffer: &T) -> Result<Framebuffer> {
         Framebuffer::create(self, buffer)
     }
 
+    /// Return the minimum and maximum framebuffer sizes the device supports,
+    /// as `((min_width, max_width), (min_height, max_height))`.
+    pub fn framebuffer_size_limits(&self) -> ((u32, u32), (u32, u32)) {
+        (self.width_limits, self.height_limits)
+    }
+
     /// Return an iterator over the list of connectors.
     pub fn connectors(&'a self) -> Connectors<'a> {
         let guard = self.connectors.lock().unwrap();
@@ -173,11 +342,24 @@ impl<'a> MasterDevice<'a> {
         Encoders::new(self, iter)
     }
 
-    /// Return an iterator over the list of display controllers.
+    /// Return an iterator over the list of available display controllers.
+    ///
+    /// Controllers are always returned in the order the device reported them,
+    /// which is the order used by the `possible_crtcs` bitmask of encoders.
     pub fn controllers(&'a self) -> DisplayControllers<'a> {
         let guard = self.controllers.lock().unwrap();
-        let iter = guard.clone().into_iter();
-        DisplayControllers::new(self, iter)
+        let ids: Vec<ControllerId> = self.controllers_order.iter()
+            .filter(| id | guard.contains(id))
+            .cloned()
+            .collect();
+        DisplayControllers::new(self, ids.into_iter())
+    }
+
+    /// Return the position of a display controller in the device's resource
+    /// list. This is the bit that represents the controller in an encoder's
+    /// `possible_crtcs` bitmask.
+    pub fn controller_index(&self, id: ControllerId) -> Option<usize> {
+        self.controllers_order.iter().position(| x | *x == id)
     }
 
     /// Attempt to load a `Connector` with the given `ConnectorId`.
@@ -205,10 +387,14 @@ impl<'a> MasterDevice<'a> {
             device: self,
             id: raw.raw.connector_id,
             interface: ConnectorInterface::from(raw.raw.connector_type),
+            interface_id: raw.raw.connector_type_id,
             state: ConnectorState::from(raw.raw.connection),
+            encoder: raw.raw.encoder_id,
             encoders: raw.encoders.clone(),
             modes: raw.modes.iter().map(| raw | Mode::from(*raw)).collect(),
-            size: (raw.raw.mm_width, raw.raw.mm_height)
+            size: (raw.raw.mm_width, raw.raw.mm_height),
+            properties: raw.properties.iter().cloned()
+                .zip(raw.prop_values.iter().cloned()).collect()
         };
 
         Ok(connector)
@@ -273,14 +459,169 @@ impl<'a> MasterDevice<'a> {
 
         let raw = try!(ffi::DrmModeGetCrtc::new(self.handle.as_raw_fd(), id));
 
+        let mode = match raw.raw.mode_valid {
+            0 => None,
+            _ => Some(Mode::from(raw.raw.mode))
+        };
+        let framebuffer = match raw.raw.fb_id {
+            0 => None,
+            id => Some(id)
+        };
+
         let controller = DisplayController {
             device: self,
             id: raw.raw.crtc_id,
+            position: (raw.raw.x, raw.raw.y),
+            framebuffer: framebuffer,
+            mode: mode,
+            gamma_size: raw.raw.gamma_size
         };
 
         Ok(controller)
     }
 
+    /// Choose a `DisplayController` to drive the given `Connector`.
+    ///
+    /// Controllers are chosen in this order:
+    ///
+    /// 1. The controller already driving the connector, since keeping it
+    ///    avoids a full reconfiguration when only the mode changes.
+    /// 2. An idle controller, one with no mode set, that is compatible with
+    ///    one of the connector's available encoders.
+    ///
+    /// Controllers driving other outputs are never chosen, as setting one
+    /// would take its output over. Load such a controller explicitly with
+    /// `MasterDevice::controller` to do that.
+    ///
+    /// # Errors
+    ///
+    /// `Error::NotAvailable` - Returned if the controller driving the
+    /// connector has already been taken, or if no idle compatible controller
+    /// is available.
+    pub fn assign_controller(&'a self, connector: &Connector<'a>) -> Result<DisplayController<'a>> {
+        if let Some(controller) = try!(connector.assigned_controller()) {
+            return Ok(controller);
+        }
+
+        for encoder in connector.encoders() {
+            let encoder = match encoder {
+                Ok(encoder) => encoder,
+                Err(e) => match *e.kind() {
+                    ErrorKind::NotAvailable => continue,
+                    _ => return Err(e)
+                }
+            };
+            for controller in encoder.controllers() {
+                match controller {
+                    Ok(controller) => {
+                        if controller.mode().is_none() {
+                            return Ok(controller);
+                        }
+                    },
+                    Err(e) => match *e.kind() {
+                        ErrorKind::NotAvailable => continue,
+                        _ => return Err(e)
+                    }
+                }
+            }
+        }
+
+        Err(ErrorKind::NotAvailable.into())
+    }
+
+    /// Set a property of any mode object using the legacy property ioctl.
+    ///
+    /// This applies immediately and is intended for drivers without atomic
+    /// modesetting, where it is the only way to change properties of
+    /// controllers and planes. Where atomic modesetting is available it
+    /// should be preferred.
+    pub fn set_object_property_legacy(&self, obj: ResourceId, obj_type: ObjectType,
+                                      prop: PropertyId, value: u64) -> Result<()> {
+        let fd = self.handle.as_raw_fd();
+        try!(ffi::DrmModeObjSetProperty::new(fd, obj, obj_type as u32, prop, value));
+        Ok(())
+    }
+
+    /// Return a summary of every connected output and how it is currently
+    /// being driven. This does not take ownership of any resources, so it can
+    /// be called while they are in use.
+    ///
+    /// Connectors are not reprobed, and any that disappear while the summary
+    /// is being built are skipped. If the encoder or controller driving an
+    /// output disappears, the output is listed without them. The framebuffer
+    /// size is omitted if the framebuffer could not be queried, such as when
+    /// it belongs to another client.
+    pub fn summary(&self) -> Result<DisplaySummary> {
+        let fd = self.handle.as_raw_fd();
+        let res = try!(ffi::DrmModeCardRes::new(fd));
+        let mut outputs = Vec::new();
+
+        for id in res.connectors.iter() {
+            // A connector can disappear after the resources were read, such
+            // as when a DP-MST display is unplugged
+            let con = match ffi::DrmModeGetConnector::current(fd, *id) {
+                Ok(con) => con,
+                Err(e) => match *e.kind() {
+                    ErrorKind::Ioctl(_, libc::ENOENT) => continue,
+                    _ => return Err(e)
+                }
+            };
+            let state = ConnectorState::from(con.raw.connection);
+            if state != ConnectorState::Connected {
+                continue;
+            }
+
+            let interface = ConnectorInterface::from(con.raw.connector_type);
+            let mut output = OutputSummary {
+                connector: *id,
+                name: format!("{}-{}", interface.name(), con.raw.connector_type_id),
+                size: (con.raw.mm_width, con.raw.mm_height),
+                controller: None,
+                mode: None,
+                framebuffer_size: None
+            };
+
+            // The encoder or controller can also go away, in which case the
+            // output is reported without them
+            if con.raw.encoder_id != 0 {
+                let enc = match ffi::DrmModeGetEncoder::new(fd, con.raw.encoder_id) {
+                    Ok(enc) => Some(enc),
+                    Err(e) => match *e.kind() {
+                        ErrorKind::Ioctl(_, libc::ENOENT) => None,
+                        _ => return Err(e)
+                    }
+                };
+                let crtc = match enc {
+                    Some(ref enc) if enc.raw.crtc_id != 0 => {
+                        match ffi::DrmModeGetCrtc::new(fd, enc.raw.crtc_id) {
+                            Ok(crtc) => Some(crtc),
+                            Err(e) => match *e.kind() {
+                                ErrorKind::Ioctl(_, libc::ENOENT) => None,
+                                _ => return Err(e)
+                            }
+                        }
+                    },
+                    _ => None
+                };
+                if let Some(crtc) = crtc {
+                    output.controller = Some(crtc.raw.crtc_id);
+                    if crtc.raw.mode_valid != 0 {
+                        output.mode = Some(Mode::from(crtc.raw.mode));
+                    }
+                    if crtc.raw.fb_id != 0 {
+                        output.framebuffer_size = ffi::DrmModeGetFb::new(fd, crtc.raw.fb_id)
+                            .ok()
+                            .map(| fb | (fb.raw.width, fb.raw.height));
+                    }
+                }
+            }
+
+            outputs.push(output);
+        }
+
+        Ok(DisplaySummary { outputs: outputs })
+    }
+
     fn unload_connector(&'a self, id: ConnectorId) {
         let mut guard = self.connectors.lock().unwrap();
         guard.push(id);
@@ -306,14 +647,30 @@ pub struct Framebuffer<'a> {
 }
 
 impl<'a> Framebuffer<'a> {
+    /// Returns true if the `(bpp, depth)` pair is one the kernel can translate
+    /// into a pixel format for a legacy framebuffer.
+    fn valid_format(bpp: u8, depth: u8) -> bool {
+        match (bpp, depth) {
+            (8, 8) => true,
+            (16, 15) | (16, 16) => true,
+            (24, 24) => true,
+            (32, 24) | (32, 30) | (32, 32) => true,
+            _ => false
+        }
+    }
+
     fn create<T: Buffer>(device: &'a MasterDevice<'a>, buffer: &T) -> Result<Self> {
         let (width, height) = buffer.size();
         let depth = buffer.depth();
         let bpp = buffer.bpp();
+        if !Self::valid_format(bpp, depth) {
+            return Err(ErrorKind::InvalidFormat(bpp, depth).into());
+        }
         let pitch = buffer.pitch();
         let handle = buffer.handle();
         let fd = device.handle.as_raw_fd();
-        let raw = try!(ffi::DrmModeAddFb::new(fd, width, height, depth, bpp, pitch, handle));
+        let raw = try!(ffi::DrmModeAddFb::new(fd, width, height, depth, bpp, pitch, handle)
+                       .map_err(exhausted));
         let fb = Framebuffer {
             device: device,
             id: raw.raw.fb_id
@@ -334,10 +691,13 @@ pub struct Connector<'a> {
     device: &'a MasterDevice<'a>,
     id: ConnectorId,
     interface: ConnectorInterface,
+    interface_id: u32,
     state: ConnectorState,
+    encoder: EncoderId,
     encoders: Vec<EncoderId>,
     modes: Vec<Mode>,
-    size: (u32, u32)
+    size: (u32, u32),
+    properties: Vec<(PropertyId, u64)>
 }
 
 impl<'a> Connector<'a> {
@@ -346,6 +706,11 @@ impl<'a> Connector<'a> {
         self.interface
     }
 
+    /// Returns the name the kernel gives the connector, such as `DP-1`.
+    pub fn name(&self) -> String {
+        format!("{}-{}", self.interface.name(), self.interface_id)
+    }
+
     /// Returns the current connection state of the connector.
     pub fn state(&self) -> ConnectorState {
         self.state
@@ -359,6 +724,21 @@ impl<'a> Connector<'a> {
         }
     }
 
+    /// Return the `Encoder` that was attached to this `Connector` when it was
+    /// loaded, or `None` if it had no active encoder.
+    ///
+    /// # Errors
+    ///
+    /// `Error::NotAvailable` - Returned if ownership of the encoder has
+    /// already been taken.
+    pub fn current_encoder(&self) -> Result<Option<Encoder<'a>>> {
+        if self.encoder == 0 {
+            return Ok(None);
+        }
+        let encoder = try!(self.device.encoder(self.encoder));
+        Ok(Some(encoder))
+    }
+
     /// Return a list of display modes that this `Connector` can support.
     pub fn modes(&self) -> Vec<Mode> {
         self.modes.clone()
@@ -368,6 +748,356 @@ impl<'a> Connector<'a> {
     pub fn size(&self) -> (u32, u32) {
         self.size
     }
+
+    /// Set a property of this `Connector` using the legacy property ioctl.
+    ///
+    /// This applies immediately and is intended for drivers without atomic
+    /// modesetting. Where atomic modesetting is available it should be
+    /// preferred, as it can apply several changes together.
+    pub fn set_property_legacy(&self, prop: PropertyId, value: u64) -> Result<()> {
+        let fd = self.device.handle.as_raw_fd();
+        try!(ffi::DrmModeConnectorSetProperty::new(fd, self.id, prop, value));
+        Ok(())
+    }
+
+    /// Return the properties of this `Connector` and their values, as they
+    /// were when the `Connector` was loaded.
+    pub fn properties(&self) -> &[(PropertyId, u64)] {
+        &self.properties
+    }
+
+    /// Look up a property of this `Connector` by name, returning its metadata
+    /// and current value if the driver exposes it. The value is read from the
+    /// device, so it reflects changes made since the `Connector` was loaded.
+    fn property(&self, name: &str) -> Result<Option<(ffi::DrmModeGetProperty, u64)>> {
+        let fd = self.device.handle.as_raw_fd();
+        let raw = try!(ffi::DrmModeGetConnector::current(fd, self.id));
+        for (&id, &value) in raw.properties.iter().zip(raw.prop_values.iter()) {
+            let prop = try!(ffi::DrmModeGetProperty::new(fd, id));
+            let prop_name = unsafe { CStr::from_ptr(prop.raw.name.as_ptr()) };
+            if prop_name.to_bytes() == name.as_bytes() {
+                return Ok(Some((prop, value)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Look up an enum property of this `Connector` by name, returning the name
+    /// of its current value.
+    fn enum_property(&self, name: &str) -> Result<Option<String>> {
+        let (prop, value) = match try!(self.property(name)) {
+            Some(p) => p,
+            None => return Ok(None)
+        };
+        let entry = prop.enums.iter().find(| entry | entry.value == value);
+        Ok(entry.map(| entry | unsafe {
+            CStr::from_ptr(entry.name.as_ptr()).to_string_lossy().into_owned()
+        }))
+    }
+
+    /// Set an enum property of this `Connector` to the value with the given
+    /// name, using the legacy property ioctl.
+    ///
+    /// # Errors
+    ///
+    /// `ErrorKind::Unsupported` - Returned if the connector does not have the
+    /// property, or the property has no value with the given name.
+    fn set_enum_property(&self, name: &'static str, value: &str) -> Result<()> {
+        let prop = match try!(self.property(name)) {
+            Some((prop, _)) => prop,
+            None => return Err(ErrorKind::Unsupported(name).into())
+        };
+        let entry = prop.enums.iter().find(| entry | {
+            unsafe { CStr::from_ptr(entry.name.as_ptr()) }.to_bytes() == value.as_bytes()
+        });
+        match entry {
+            Some(entry) => self.set_property_legacy(prop.raw.prop_id, entry.value),
+            None => Err(ErrorKind::Unsupported(name).into())
+        }
+    }
+
+    /// Set a range property of this `Connector`, clamping the value to the
+    /// range the driver allows, using the legacy property ioctl. Returns the
+    /// value that was set.
+    ///
+    /// # Errors
+    ///
+    /// `ErrorKind::Unsupported` - Returned if the connector does not have the
+    /// property.
+    fn set_range_property(&self, name: &'static str, value: u64) -> Result<u64> {
+        let prop = match try!(self.property(name)) {
+            Some((prop, _)) => prop,
+            None => return Err(ErrorKind::Unsupported(name).into())
+        };
+        // Range properties report their minimum and maximum as their values
+        let value = if prop.values.len() == 2 {
+            let (min, max) = (prop.values[0], prop.values[1]);
+            if value < min { min } else if value > max { max } else { value }
+        } else {
+            value
+        };
+        try!(self.set_property_legacy(prop.raw.prop_id, value));
+        Ok(value)
+    }
+
+    /// Returns `true` if the driver marked this `Connector` as not intended
+    /// for desktop use, as is the case for VR headsets. Such connectors should
+    /// be left out of the desktop and reserved for leasing.
+    pub fn is_non_desktop(&self) -> Result<bool> {
+        let prop = try!(self.property("non-desktop"));
+        Ok(prop.map_or(false, | (_, value) | value != 0))
+    }
+
+    /// Return the physical orientation of the panel behind this `Connector`.
+    /// Content should be rotated to compensate so that it appears upright.
+    pub fn panel_orientation(&self) -> Result<PanelOrientation> {
+        let name = try!(self.enum_property("panel orientation"));
+        let orientation = match name.as_ref().map(| n | n.as_str()) {
+            Some("Normal") => PanelOrientation::Normal,
+            Some("Upside Down") => PanelOrientation::UpsideDown,
+            Some("Left Side Up") => PanelOrientation::LeftSideUp,
+            Some("Right Side Up") => PanelOrientation::RightSideUp,
+            _ => PanelOrientation::Unknown
+        };
+        Ok(orientation)
+    }
+
+    /// Return the state of the electronic privacy screen built into the panel
+    /// behind this `Connector`, or `None` if it has none.
+    pub fn privacy_screen(&self) -> Result<Option<PrivacyScreenState>> {
+        let hw = match try!(self.enum_property("privacy-screen hw-state")) {
+            Some(hw) => hw,
+            None => return Ok(None)
+        };
+        let sw = try!(self.enum_property("privacy-screen sw-state"));
+        let state = PrivacyScreenState {
+            enabled: hw.starts_with("Enabled"),
+            locked: hw.ends_with(", locked"),
+            requested: sw.map_or(false, | sw | sw == "Enabled")
+        };
+        Ok(Some(state))
+    }
+
+    /// Request that the privacy screen of this `Connector` be turned on or
+    /// off.
+    ///
+    /// This only sets the software request. If the screen is locked, such as
+    /// by a hardware switch or the firmware, the request is kept but has no
+    /// effect until the lock is released. Read `privacy_screen` afterwards to
+    /// see the state the panel is actually in.
+    ///
+    /// # Errors
+    ///
+    /// `ErrorKind::Unsupported` - Returned if the connector has no privacy
+    /// screen.
+    pub fn set_privacy_screen(&self, enable: bool) -> Result<()> {
+        let value = if enable { "Enabled" } else { "Disabled" };
+        self.set_enum_property("privacy-screen sw-state", value)
+    }
+
+    /// Return the content protection (HDCP) state of this `Connector`, or
+    /// `None` if the driver does not support content protection on it.
+    ///
+    /// The kernel changes this state on its own: a `Desired` request becomes
+    /// `Enabled` asynchronously once the link is authenticated, and falls back
+    /// to `Desired` if protection is lost, such as when the sink is unplugged.
+    /// Callers must read it again rather than assume a request took effect.
+    pub fn content_protection(&self) -> Result<Option<ContentProtection>> {
+        let name = try!(self.enum_property("Content Protection"));
+        let state = name.and_then(| name | match name.as_str() {
+            "Undesired" => Some(ContentProtection::Undesired),
+            "Desired" => Some(ContentProtection::Desired),
+            "Enabled" => Some(ContentProtection::Enabled),
+            _ => None
+        });
+        Ok(state)
+    }
+
+    /// Request content protection (HDCP) to be turned on or off for this
+    /// `Connector`, using the legacy property ioctl.
+    ///
+    /// Request `Desired` to turn protection on; the kernel reports `Enabled`
+    /// once it has taken effect, which happens asynchronously (see
+    /// `content_protection`). The kernel does not accept `Enabled` as a
+    /// request.
+    ///
+    /// # Errors
+    ///
+    /// `ErrorKind::Unsupported` - Returned if the connector does not support
+    /// content protection.
+    pub fn set_content_protection(&self, desired: ContentProtection) -> Result<()> {
+        let value = match desired {
+            ContentProtection::Undesired => "Undesired",
+            ContentProtection::Desired => "Desired",
+            ContentProtection::Enabled => "Enabled"
+        };
+        self.set_enum_property("Content Protection", value)
+    }
+
+    /// Return a helper for the properties of analog TV outputs, such as
+    /// composite and S-Video connectors.
+    pub fn tv_properties<'b>(&'b self) -> TvProperties<'a, 'b> {
+        TvProperties { connector: self }
+    }
+
+    /// Return what is attached downstream of this `Connector`, such as a DVI
+    /// sink behind a DisplayPort adapter. Returns `None` if the driver does
+    /// not report a subconnector for this kind of connector.
+    pub fn subconnector(&self) -> Result<Option<SubConnector>> {
+        // DisplayPort connectors use their own property name
+        let mut name = try!(self.enum_property("subconnector"));
+        if name.is_none() {
+            name = try!(self.enum_property("DP Subconnector"));
+        }
+        let subconnector = name.map(| name | match name.as_str() {
+            "DVI-D" => SubConnector::DVID,
+            "DVI-A" => SubConnector::DVIA,
+            "Composite" => SubConnector::Composite,
+            "SVIDEO" => SubConnector::SVideo,
+            "Component" => SubConnector::Component,
+            "SCART" => SubConnector::SCART,
+            "VGA" => SubConnector::VGA,
+            "HDMI" => SubConnector::HDMI,
+            "DP" => SubConnector::DisplayPort,
+            "Wireless" => SubConnector::Wireless,
+            "Native" => SubConnector::Native,
+            _ => SubConnector::Unknown
+        });
+        Ok(subconnector)
+    }
+
+    /// Return the distinct resolutions this `Connector` supports, sorted and
+    /// with duplicate refresh rates collapsed.
+    pub fn resolutions(&self) -> Vec<(u16, u16)> {
+        let mut resolutions: Vec<(u16, u16)> =
+            self.modes.iter().map(| mode | mode.display).collect();
+        resolutions.sort();
+        resolutions.dedup();
+        resolutions
+    }
+
+    /// Return the refresh rates, in Hz, available at the given resolution.
+    pub fn refresh_rates(&self, res: (u16, u16)) -> Vec<f64> {
+        let mut rates: Vec<f64> = self.modes.iter()
+            .filter(| mode | mode.display == res)
+            .map(| mode | mode.refresh_rate())
+            .collect();
+        rates.sort_by(| a, b | a.partial_cmp(b).unwrap());
+        rates.dedup();
+        rates
+    }
+
+    /// Return the display modes of this `Connector` that pass the given
+    /// `ModeFilter`.
+    pub fn modes_filtered(&self, filter: ModeFilter) -> Vec<Mode> {
+        self.modes.iter()
+            .filter(| mode | filter.accepts(mode))
+            .cloned()
+            .collect()
+    }
+
+    /// Return the display modes of this `Connector` ordered for presentation.
+    /// The preferred mode comes first, followed by the remaining modes from
+    /// largest to smallest resolution and highest to lowest refresh rate.
+    pub fn modes_sorted(&self) -> Vec<Mode> {
+        let mut modes = self.modes.clone();
+        mode::sort_preferred_first(&mut modes);
+        modes
+    }
+
+    /// Return the `DisplayController` currently driving this `Connector`, or
+    /// `None` if the connector is not in use. The assignment is read from the
+    /// device at the time of the call.
+    ///
+    /// # Errors
+    ///
+    /// `Error::NotAvailable` - Returned if ownership of the controller has
+    /// already been taken.
+    pub fn assigned_controller(&self) -> Result<Option<DisplayController<'a>>> {
+        let fd = self.device.handle.as_raw_fd();
+        let raw = try!(ffi::DrmModeGetConnector::current(fd, self.id));
+        if raw.raw.encoder_id == 0 {
+            return Ok(None);
+        }
+        let enc = try!(ffi::DrmModeGetEncoder::new(fd, raw.raw.encoder_id));
+        if enc.raw.crtc_id == 0 {
+            return Ok(None);
+        }
+        let controller = try!(self.device.controller(enc.raw.crtc_id));
+        Ok(Some(controller))
+    }
+
+    /// Return the `DisplayController` driving this `Connector` along with the
+    /// mode it is set to, or `None` if the connector is not being driven.
+    ///
+    /// # Errors
+    ///
+    /// `Error::NotAvailable` - Returned if ownership of the controller has
+    /// already been taken.
+    pub fn current_config(&self) -> Result<Option<(DisplayController<'a>, Mode)>> {
+        let controller = match try!(self.assigned_controller()) {
+            Some(controller) => controller,
+            None => return Ok(None)
+        };
+        match controller.mode() {
+            Some(mode) => Ok(Some((controller, mode))),
+            None => Ok(None)
+        }
+    }
+
+    /// Check that the given mode can be used with this `Connector`. A mode is
+    /// accepted if its timings match one of the connector's modes, regardless
+    /// of its name or type flags. Custom modes, such as modelines, are also
+    /// accepted if their timings are well formed (see `Mode::has_valid_timings`)
+    /// and they ask no more of the connector than its listed modes do: the
+    /// resolution must fit within the largest listed resolution and the pixel
+    /// clock must not exceed the highest listed clock. If the connector lists
+    /// no modes, custom modes are bounded by the device's framebuffer size
+    /// limits instead.
+    ///
+    /// # Errors
+    ///
+    /// `ErrorKind::UnsupportedMode` - Returned if the mode is not listed and
+    /// is malformed or out of the connector's range.
+    pub fn validate_mode(&self, mode: &Mode) -> Result<()> {
+        let found = self.modes.iter().any(| m | {
+            m.clock == mode.clock &&
+            m.display == mode.display &&
+            m.hsync == mode.hsync &&
+            m.vsync == mode.vsync &&
+            m.hskew == mode.hskew &&
+            m.vscan == mode.vscan &&
+            m.htotal == mode.htotal &&
+            m.vtotal == mode.vtotal &&
+            m.flags == mode.flags
+        });
+        if found {
+            return Ok(());
+        }
+
+        let (width, height) = (mode.display.0 as u32, mode.display.1 as u32);
+        let in_range = if self.modes.is_empty() {
+            let ((_, max_width), (_, max_height)) = self.device.framebuffer_size_limits();
+            width <= max_width && height <= max_height
+        } else {
+            let max_width = self.modes.iter().map(| m | m.display.0 as u32).max().unwrap();
+            let max_height = self.modes.iter().map(| m | m.display.1 as u32).max().unwrap();
+            let max_clock = self.modes.iter().map(| m | m.clock).max().unwrap();
+            width <= max_width && height <= max_height && mode.clock <= max_clock
+        };
+        if in_range && mode.has_valid_timings() {
+            return Ok(());
+        }
+
+        let name = format!("{}x{}@{:.0}", width, height, mode.refresh_rate());
+        Err(ErrorKind::UnsupportedMode(name, self.name()).into())
+    }
+
+    /// Return the supported mode closest to the requested resolution and,
+    /// optionally, refresh rate. See `mode::nearest` for how ties are broken.
+    pub fn nearest_mode(&self, width: u16, height: u16, refresh: Option<f64>) -> Option<Mode> {
+        mode::nearest(&self.modes, width, height, refresh).cloned()
+    }
 }
 
 impl<'a> Drop for Connector<'a> {
@@ -376,6 +1106,83 @@ impl<'a> Drop for Connector<'a> {
     }
 }
 
+/// Typed access to the properties of analog TV outputs, as returned by
+/// `Connector::tv_properties`. Each getter returns `None` if the connector
+/// does not have the property, and each setter fails with
+/// `ErrorKind::Unsupported`. Changes are applied with the legacy property
+/// ioctl.
+pub struct TvProperties<'a: 'b, 'b> {
+    connector: &'b Connector<'a>
+}
+
+impl<'a, 'b> TvProperties<'a, 'b> {
+    /// Returns the name of the TV mode the output is set to, such as `PAL`.
+    pub fn mode(&self) -> Result<Option<String>> {
+        self.connector.enum_property("mode")
+    }
+
+    /// Set the TV mode of the output by name.
+    pub fn set_mode(&self, name: &str) -> Result<()> {
+        self.connector.set_enum_property("mode", name)
+    }
+
+    /// Returns the name of the TV standard the output uses, on drivers that
+    /// expose it separately from the mode.
+    pub fn standard(&self) -> Result<Option<String>> {
+        self.connector.enum_property("tv standard")
+    }
+
+    /// Set the TV standard of the output by name.
+    pub fn set_standard(&self, name: &str) -> Result<()> {
+        self.connector.set_enum_property("tv standard", name)
+    }
+
+    /// Returns the current value of a range setting.
+    pub fn get(&self, setting: TvSetting) -> Result<Option<u64>> {
+        let prop = try!(self.connector.property(setting.name()));
+        Ok(prop.map(| (_, value) | value))
+    }
+
+    /// Change a range setting. The value is clamped to the range the driver
+    /// allows, and the value that was set is returned.
+    pub fn set(&self, setting: TvSetting, value: u64) -> Result<u64> {
+        self.connector.set_range_property(setting.name(), value)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// A range setting of an analog TV output.
+pub enum TvSetting {
+    LeftMargin,
+    RightMargin,
+    TopMargin,
+    BottomMargin,
+    Brightness,
+    Contrast,
+    FlickerReduction,
+    Overscan,
+    Saturation,
+    Hue
+}
+
+impl TvSetting {
+    /// Returns the name of the property behind this setting.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            TvSetting::LeftMargin => "left margin",
+            TvSetting::RightMargin => "right margin",
+            TvSetting::TopMargin => "top margin",
+            TvSetting::BottomMargin => "bottom margin",
+            TvSetting::Brightness => "brightness",
+            TvSetting::Contrast => "contrast",
+            TvSetting::FlickerReduction => "flicker reduction",
+            TvSetting::Overscan => "overscan",
+            TvSetting::Saturation => "saturation",
+            TvSetting::Hue => "hue",
+        }
+    }
+}
+
 /// An iterator over a list of `Connector` objects.
 pub struct Connectors<'a> {
     device: &'a MasterDevice<'a>,
@@ -435,14 +1242,120 @@ pub enum ConnectorState {
     Unknown = ffi::Connection::FFI_DRM_MODE_UNKNOWN as isize
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// The physical orientation of a panel relative to its housing.
+pub enum PanelOrientation {
+    /// The driver does not know the orientation of the panel.
+    Unknown,
+    /// The top of the panel is at the top of the housing.
+    Normal,
+    /// The panel is mounted upside down.
+    UpsideDown,
+    /// The panel is mounted with its left side at the top of the housing.
+    LeftSideUp,
+    /// The panel is mounted with its right side at the top of the housing.
+    RightSideUp
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// The content protection (HDCP) state of a `Connector`.
+pub enum ContentProtection {
+    /// Protection is not requested.
+    Undesired,
+    /// Protection is requested but not yet in effect.
+    Desired,
+    /// Protection is in effect.
+    Enabled
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// The state of an electronic privacy screen, as returned by
+/// `Connector::privacy_screen`.
+pub struct PrivacyScreenState {
+    /// Whether the privacy screen is currently on.
+    pub enabled: bool,
+    /// Whether the state is locked, such as by a hardware switch or the
+    /// firmware, so that software requests are ignored.
+    pub locked: bool,
+    /// Whether software last requested the privacy screen to be on. This can
+    /// differ from `enabled` while the state is locked.
+    pub requested: bool
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// What is physically attached downstream of a `Connector`.
+pub enum SubConnector {
+    Unknown,
+    DVID,
+    DVIA,
+    Composite,
+    SVideo,
+    Component,
+    SCART,
+    VGA,
+    HDMI,
+    DisplayPort,
+    Wireless,
+    /// A display using the native signaling of the `Connector`.
+    Native
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// The type of a mode object, used to identify the object a property belongs
+/// to.
+pub enum ObjectType {
+    Controller = ffi::ObjectType::FFI_DRM_MODE_OBJECT_CRTC as isize,
+    Connector = ffi::ObjectType::FFI_DRM_MODE_OBJECT_CONNECTOR as isize,
+    Encoder = ffi::ObjectType::FFI_DRM_MODE_OBJECT_ENCODER as isize,
+    Mode = ffi::ObjectType::FFI_DRM_MODE_OBJECT_MODE as isize,
+    Property = ffi::ObjectType::FFI_DRM_MODE_OBJECT_PROPERTY as isize,
+    Framebuffer = ffi::ObjectType::FFI_DRM_MODE_OBJECT_FB as isize,
+    Blob = ffi::ObjectType::FFI_DRM_MODE_OBJECT_BLOB as isize,
+    Plane = ffi::ObjectType::FFI_DRM_MODE_OBJECT_PLANE as isize,
+    Any = ffi::ObjectType::FFI_DRM_MODE_OBJECT_ANY as isize,
+}
+
+impl ConnectorInterface {
+    /// Returns the name the kernel uses for this kind of interface, such as
+    /// `HDMI-A` or `eDP`. Output names like `HDMI-A-1` are formed from it.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ConnectorInterface::Unknown => "Unknown",
+            ConnectorInterface::VGA => "VGA",
+            ConnectorInterface::DVII => "DVI-I",
+            ConnectorInterface::DVID => "DVI-D",
+            ConnectorInterface::DVIA => "DVI-A",
+            ConnectorInterface::Composite => "Composite",
+            ConnectorInterface::SVideo => "SVIDEO",
+            ConnectorInterface::LVDS => "LVDS",
+            ConnectorInterface::Component => "Component",
+            ConnectorInterface::NinePinDIN => "DIN",
+            ConnectorInterface::DisplayPort => "DP",
+            ConnectorInterface::HDMIA => "HDMI-A",
+            ConnectorInterface::HDMIB => "HDMI-B",
+            ConnectorInterface::TV => "TV",
+            ConnectorInterface::EDP => "eDP",
+            ConnectorInterface::Virtual => "Virtual",
+            ConnectorInterface::DSI => "DSI",
+        }
+    }
+}
+
 impl From<u32> for ConnectorInterface {
     fn from(ty: u32) -> ConnectorInterface {
+        // Kernels newer than these bindings may report types we don't know
+        if ty > ConnectorInterface::DSI as u32 {
+            return ConnectorInterface::Unknown;
+        }
         unsafe { transmute(ty as u8) }
     }
 }
 
 impl From<u32> for ConnectorState {
     fn from(ty: u32) -> ConnectorState {
+        if ty < ConnectorState::Connected as u32 || ty > ConnectorState::Unknown as u32 {
+            return ConnectorState::Unknown;
+        }
         unsafe { transmute(ty as u8) }
     }
 }
@@ -502,19 +1415,82 @@ impl<'a> Encoders<'a> {
 pub struct DisplayController<'a> {
     device: &'a MasterDevice<'a>,
     id: ControllerId,
+    position: (u32, u32),
+    framebuffer: Option<FramebufferId>,
+    mode: Option<Mode>,
+    gamma_size: u32
 }
 
 impl<'a, 'b, 'c, 'd> DisplayController<'a> {
+    /// Returns the position of the controller's viewport within the
+    /// `Framebuffer` it scans out.
+    pub fn position(&self) -> (u32, u32) {
+        self.position
+    }
+
+    /// Returns the id of the `Framebuffer` being scanned out, if any.
+    pub fn framebuffer(&self) -> Option<FramebufferId> {
+        self.framebuffer
+    }
+
+    /// Returns the mode the controller is set to, if any.
+    pub fn mode(&self) -> Option<Mode> {
+        self.mode.clone()
+    }
+
+    /// Returns the number of entries in the controller's legacy gamma ramp.
+    pub fn gamma_size(&self) -> u32 {
+        self.gamma_size
+    }
+
     /// Sets the controller. Unstable.
+    ///
+    /// # Errors
+    ///
+    /// `ErrorKind::UnsupportedMode` - Returned if the mode is rejected by
+    /// `Connector::validate_mode`.
     pub fn set_controller(self, fb: &'b Framebuffer,
                           connector: &'c Connector,
                           encoder: &'d Encoder, mode: Mode) -> Result<()> {
+        try!(connector.validate_mode(&mode));
         try!(
             ffi::DrmModeSetCrtc::new(self.device.handle.as_raw_fd(),
             self.id, fb.id, 0, 0, vec![connector.id], mode.into())
         );
         Ok(())
     }
+
+    /// Schedule a flip to the given `Framebuffer` on the next vertical blank.
+    ///
+    /// If `immediate` is set, the flip happens as soon as possible without
+    /// waiting for vertical blank, which may cause tearing.
+    ///
+    /// The flip completes asynchronously. When it does, the kernel queues a
+    /// flip completion event on the device, so `Device::wait_readable` returns
+    /// `true` and the event can be read from the device file. Only one flip
+    /// can be pending on a controller at a time.
+    ///
+    /// # Errors
+    ///
+    /// `ErrorKind::Unsupported` - Returned if an immediate flip was requested
+    /// but the device does not advertise `DRM_CAP_ASYNC_PAGE_FLIP`.
+    ///
+    /// `ErrorKind::Ioctl` with `EBUSY` - Returned if a previous flip on this
+    /// controller has not completed yet. Wait for its event before flipping
+    /// again.
+    pub fn page_flip(&self, fb: &Framebuffer, immediate: bool) -> Result<()> {
+        let fd = self.device.handle.as_raw_fd();
+        let mut flags = unsafe { ffi::FFI_DRM_MODE_PAGE_FLIP_EVENT };
+        if immediate {
+            let cap = try!(ffi::DrmGetCap::new(fd, unsafe { ffi::FFI_DRM_CAP_ASYNC_PAGE_FLIP }));
+            if cap.raw.value == 0 {
+                return Err(ErrorKind::Unsupported("DRM_CAP_ASYNC_PAGE_FLIP").into());
+            }
+            flags |= unsafe { ffi::FFI_DRM_MODE_PAGE_FLIP_ASYNC };
+        }
+        try!(ffi::DrmModePageFlip::new(fd, self.id, fb.id, flags));
+        Ok(())
+    }
 }
 
 impl<'a> Drop for DisplayController<'a> {
@@ -548,69 +1524,70 @@ impl<'a> DisplayControllers<'a> {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
-pub struct Mode {
-    pub name: String,
-    pub clock: u32,
-    pub display: (u16, u16),
-    pub hsync: (u16, u16),
-    pub vsync: (u16, u16),
-    pub hskew: u16,
-    pub vscan: u16,
-    pub htotal: u16,
-    pub vtotal: u16,
-    pub vrefresh: u32,
-    pub flags: u32,
-    pub mode_type: u32,
-}
-
-impl From<ffi::drm_mode_modeinfo> for Mode {
-    fn from(raw: ffi::drm_mode_modeinfo) -> Mode {
-        let name = unsafe {
-            CStr::from_ptr(raw.name.as_ptr()).to_str().unwrap()
-        };
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default)]
+/// An unsigned 16.16 fixed point number, as used by the kernel for plane
+/// source coordinates.
+pub struct Fixed16_16(u32);
+
+impl Fixed16_16 {
+    /// Create a value from a whole number.
+    pub fn from_int(value: u16) -> Fixed16_16 {
+        Fixed16_16((value as u32) << 16)
+    }
 
-        Mode {
-            name: name.to_string(),
-            clock: raw.clock,
-            display: (raw.hdisplay, raw.vdisplay),
-            hsync: (raw.hsync_start, raw.hsync_end),
-            vsync: (raw.vsync_start, raw.vsync_end),
-            hskew: raw.hskew,
-            vscan: raw.vscan,
-            htotal: raw.htotal,
-            vtotal: raw.vtotal,
-            vrefresh: raw.vrefresh,
-            flags: raw.flags,
-            mode_type: raw.type_
-        }
-    }
-}
-
-impl Into<ffi::drm_mode_modeinfo> for Mode {
-    fn into(self) -> ffi::drm_mode_modeinfo {
-        let (hdisplay, vdisplay) = self.display;
-        let (hsync_start, hsync_end) = self.hsync;
-        let (vsync_start, vsync_end) = self.vsync;
-
-        ffi::drm_mode_modeinfo {
-            name: [0; 32],
-            clock: self.clock,
-            hdisplay: hdisplay,
-            vdisplay: vdisplay,
-            hsync_start: hsync_start,
-            hsync_end: hsync_end,
-            vsync_start: vsync_start,
-            vsync_end: vsync_end,
-            hskew: self.hskew,
-            vscan: self.vscan,
-            htotal: self.htotal,
-            vtotal: self.vtotal,
-            vrefresh: self.vrefresh,
-            flags: self.flags,
-            type_: self.mode_type
+    /// Create a value from a floating point number, rounding to the nearest
+    /// representable value. Values outside the representable range are
+    /// clamped.
+    pub fn from_f64(value: f64) -> Fixed16_16 {
+        let raw = (value * 65536.0).round();
+        if raw <= 0.0 {
+            Fixed16_16(0)
+        } else if raw >= u32::max_value() as f64 {
+            Fixed16_16(u32::max_value())
+        } else {
+            Fixed16_16(raw as u32)
         }
     }
+
+    /// Create a value from its raw 16.16 representation.
+    pub fn from_raw(raw: u32) -> Fixed16_16 {
+        Fixed16_16(raw)
+    }
+
+    /// Return the raw 16.16 representation.
+    pub fn to_raw(self) -> u32 {
+        self.0
+    }
+
+    /// Return the value as a floating point number.
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / 65536.0
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A snapshot of the connected outputs of a device, as returned by
+/// `MasterDevice::summary`.
+pub struct DisplaySummary {
+    pub outputs: Vec<OutputSummary>
+}
+
+#[derive(Debug, Clone)]
+/// The configuration of a single connected output.
+pub struct OutputSummary {
+    /// The id of the `Connector`.
+    pub connector: ConnectorId,
+    /// The name of the connector as the kernel reports it, such as
+    /// `HDMI-A-1`.
+    pub name: String,
+    /// The physical size of the display in millimeters.
+    pub size: (u32, u32),
+    /// The `DisplayController` currently driving the output, if any.
+    pub controller: Option<ControllerId>,
+    /// The mode currently set on the controller, if any.
+    pub mode: Option<Mode>,
+    /// The size of the framebuffer being scanned out, if any.
+    pub framebuffer_size: Option<(u32, u32)>
 }
 
 /// An object that implements the `Buffer` trait allows it to be used as a part
@@ -629,5 +1606,3 @@ pub trait Buffer {
     /// libgbm.
     fn handle(&self) -> u32;
 }
-
-
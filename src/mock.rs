@@ -0,0 +1,482 @@
+//! A mock device for testing without hardware.
+//!
+//! With the `mock` feature enabled, the ioctls this crate makes are answered
+//! from a `MockCard` held by the current thread instead of being sent to the
+//! kernel. Any file can then be opened as a `Device`, such as `/dev/null`, and
+//! used to exercise enumeration, property and mode handling.
+//!
+//! Each thread starts out with `MockCard::sample()`. Use `install` to replace
+//! it with a different card.
+
+use super::{ConnectorId, EncoderId, ControllerId, FramebufferId, PropertyId};
+use super::{ConnectorInterface, ConnectorState, Mode};
+use ffi;
+use fixtures;
+
+/// The resources of a mock device.
+#[derive(Debug, Clone)]
+pub struct MockCard {
+    pub connectors: Vec<MockConnector>,
+    pub encoders: Vec<MockEncoder>,
+    /// Controllers in the order they are reported, which is the order used by
+    /// `MockEncoder::possible_controllers`.
+    pub controllers: Vec<MockController>,
+    pub properties: Vec<MockProperty>,
+    /// Device capabilities, as `(capability, value)` pairs.
+    pub capabilities: Vec<(u64, u64)>,
+    pub width_limits: (u32, u32),
+    pub height_limits: (u32, u32)
+}
+
+/// A mock `Connector`.
+#[derive(Debug, Clone)]
+pub struct MockConnector {
+    pub id: ConnectorId,
+    pub interface: ConnectorInterface,
+    /// The index of the connector among those with the same interface.
+    pub interface_id: u32,
+    pub state: ConnectorState,
+    /// The encoder currently in use, or 0 if there is none.
+    pub encoder: EncoderId,
+    pub encoders: Vec<EncoderId>,
+    pub modes: Vec<Mode>,
+    pub size: (u32, u32),
+    /// The properties of the connector, as `(property, value)` pairs.
+    pub properties: Vec<(PropertyId, u64)>
+}
+
+/// A mock `Encoder`.
+#[derive(Debug, Clone)]
+pub struct MockEncoder {
+    pub id: EncoderId,
+    /// The controller currently in use, or 0 if there is none.
+    pub controller: ControllerId,
+    /// A bitmask of compatible controllers, by their position in
+    /// `MockCard::controllers`.
+    pub possible_controllers: u32
+}
+
+/// A mock `DisplayController`.
+#[derive(Debug, Clone)]
+pub struct MockController {
+    pub id: ControllerId,
+    /// The framebuffer being scanned out, or 0 if there is none.
+    pub framebuffer: FramebufferId,
+    pub position: (u32, u32),
+    pub mode: Option<Mode>,
+    pub gamma_size: u32
+}
+
+/// A mock property. Enum properties list the name of each value.
+#[derive(Debug, Clone)]
+pub struct MockProperty {
+    pub id: PropertyId,
+    pub name: String,
+    pub values: Vec<u64>,
+    pub enums: Vec<(u64, String)>
+}
+
+impl MockCard {
+    /// A card with two controllers and a single encoder shared by two
+    /// connectors:
+    ///
+    /// * A connected DisplayPort connector (id 30) driven by controller 10,
+    ///   with three modes, a DisplayPort subconnector reporting HDMI and
+    ///   content protection turned off.
+    /// * A disconnected eDP connector (id 31) whose panel is mounted right
+    ///   side up and has a privacy screen that is turned off.
+    pub fn sample() -> MockCard {
+        let modes = vec![
+            fixtures::mode(1920, 1080, 60, true),
+            fixtures::mode(1920, 1080, 50, false),
+            fixtures::mode(1280, 720, 60, false)
+        ];
+
+        let non_desktop = MockProperty {
+            id: 40,
+            name: "non-desktop".to_string(),
+            values: vec![0, 1],
+            enums: Vec::new()
+        };
+        let subconnector = MockProperty {
+            id: 41,
+            name: "DP Subconnector".to_string(),
+            values: vec![0, 1, 3, 11],
+            enums: vec![(0, "Unknown".to_string()),
+                        (1, "VGA".to_string()),
+                        (3, "HDMI".to_string()),
+                        (11, "Native".to_string())]
+        };
+        let orientation = MockProperty {
+            id: 42,
+            name: "panel orientation".to_string(),
+            values: vec![0, 1, 2, 3],
+            enums: vec![(0, "Normal".to_string()),
+                        (1, "Upside Down".to_string()),
+                        (2, "Left Side Up".to_string()),
+                        (3, "Right Side Up".to_string())]
+        };
+
+        let privacy_sw = MockProperty {
+            id: 43,
+            name: "privacy-screen sw-state".to_string(),
+            values: vec![0, 1],
+            enums: vec![(0, "Disabled".to_string()),
+                        (1, "Enabled".to_string())]
+        };
+        let privacy_hw = MockProperty {
+            id: 44,
+            name: "privacy-screen hw-state".to_string(),
+            values: vec![0, 1, 2, 3],
+            enums: vec![(0, "Disabled".to_string()),
+                        (1, "Enabled".to_string()),
+                        (2, "Disabled, locked".to_string()),
+                        (3, "Enabled, locked".to_string())]
+        };
+
+        let content_protection = MockProperty {
+            id: 45,
+            name: "Content Protection".to_string(),
+            values: vec![0, 1, 2],
+            enums: vec![(0, "Undesired".to_string()),
+                        (1, "Desired".to_string()),
+                        (2, "Enabled".to_string())]
+        };
+
+        MockCard {
+            connectors: vec![
+                MockConnector {
+                    id: 30,
+                    interface: ConnectorInterface::DisplayPort,
+                    interface_id: 1,
+                    state: ConnectorState::Connected,
+                    encoder: 20,
+                    encoders: vec![20],
+                    modes: modes.clone(),
+                    size: (520, 290),
+                    properties: vec![(40, 0), (41, 3), (45, 0)]
+                },
+                MockConnector {
+                    id: 31,
+                    interface: ConnectorInterface::EDP,
+                    interface_id: 1,
+                    state: ConnectorState::Disconnected,
+                    encoder: 0,
+                    encoders: vec![20],
+                    modes: Vec::new(),
+                    size: (0, 0),
+                    properties: vec![(40, 0), (42, 3), (43, 0), (44, 0)]
+                }
+            ],
+            encoders: vec![
+                MockEncoder { id: 20, controller: 10, possible_controllers: 0b11 }
+            ],
+            controllers: vec![
+                MockController {
+                    id: 10,
+                    framebuffer: 50,
+                    position: (0, 0),
+                    mode: Some(modes[0].clone()),
+                    gamma_size: 256
+                },
+                MockController {
+                    id: 11,
+                    framebuffer: 0,
+                    position: (0, 0),
+                    mode: None,
+                    gamma_size: 256
+                }
+            ],
+            properties: vec![non_desktop, subconnector, orientation,
+                             privacy_sw, privacy_hw, content_protection],
+            capabilities: Vec::new(),
+            width_limits: (0, 8192),
+            height_limits: (0, 8192)
+        }
+    }
+}
+
+/// Replace the mock card used by the current thread.
+pub fn install(card: MockCard) {
+    ffi::mock::install(card);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::*;
+
+    fn open() -> Device {
+        install(MockCard::sample());
+        Device::open("/dev/null").unwrap()
+    }
+
+    #[test]
+    fn enumerates_resources() {
+        let dev = open();
+        let master = dev.lock_master().unwrap();
+        assert_eq!(master.framebuffer_size_limits(), ((0, 8192), (0, 8192)));
+
+        let connectors: Vec<_> = master.connectors().map(| c | c.unwrap().id).collect();
+        assert_eq!(connectors, vec![30, 31]);
+
+        let encoder = master.encoders().next().unwrap().unwrap();
+        let controllers: Vec<_> = encoder.controllers().map(| c | c.unwrap().id).collect();
+        assert_eq!(controllers, vec![10, 11]);
+    }
+
+    #[test]
+    fn reads_connector_modes() {
+        let dev = open();
+        let master = dev.lock_master().unwrap();
+        let connector = master.connector(30).unwrap();
+        assert_eq!(connector.interface(), ConnectorInterface::DisplayPort);
+        assert_eq!(connector.state(), ConnectorState::Connected);
+        assert_eq!(connector.modes().len(), 3);
+
+        let sorted = connector.modes_sorted();
+        assert!(sorted[0].is_preferred());
+        assert_eq!(sorted[0].name, "1920x1080");
+        assert_eq!(sorted[1].vrefresh, 50);
+
+        let nearest = connector.nearest_mode(1280, 720, Some(60.0)).unwrap();
+        assert_eq!(nearest.display, (1280, 720));
+    }
+
+    #[test]
+    fn decodes_connector_properties() {
+        let dev = open();
+        let master = dev.lock_master().unwrap();
+
+        let dp = master.connector(30).unwrap();
+        assert!(!dp.is_non_desktop().unwrap());
+        assert_eq!(dp.subconnector().unwrap(), Some(SubConnector::HDMI));
+        assert_eq!(dp.panel_orientation().unwrap(), PanelOrientation::Unknown);
+
+        let edp = master.connector(31).unwrap();
+        assert_eq!(edp.subconnector().unwrap(), None);
+        assert_eq!(edp.panel_orientation().unwrap(), PanelOrientation::RightSideUp);
+    }
+
+    #[test]
+    fn reads_non_desktop_flag() {
+        let mut card = MockCard::sample();
+        card.connectors[0].properties[0].1 = 1;
+        install(card);
+        let dev = Device::open("/dev/null").unwrap();
+        let master = dev.lock_master().unwrap();
+        assert!(master.connector(30).unwrap().is_non_desktop().unwrap());
+    }
+
+    #[test]
+    fn reports_current_config() {
+        let dev = open();
+        let master = dev.lock_master().unwrap();
+        let connector = master.connector(30).unwrap();
+        let (controller, mode) = connector.current_config().unwrap().unwrap();
+        assert_eq!(controller.id, 10);
+        assert_eq!(controller.framebuffer(), Some(50));
+        assert_eq!(mode.display, (1920, 1080));
+        assert!(master.connector(31).unwrap().current_config().unwrap().is_none());
+    }
+
+    #[test]
+    fn missing_connector_is_an_ioctl_error() {
+        let mut card = MockCard::sample();
+        card.connectors.pop();
+        let dev = open();
+        let master = dev.lock_master().unwrap();
+        install(card);
+        let err = master.connector(31).err().expect("connector 31 should be gone");
+        match *err.kind() {
+            ErrorKind::Ioctl("MODE_GETCONNECTOR", errno) => assert_eq!(errno, libc::ENOENT),
+            ref kind => panic!("unexpected error {:?}", kind)
+        }
+    }
+
+    #[test]
+    fn summarizes_connected_outputs() {
+        let dev = open();
+        let master = dev.lock_master().unwrap();
+        let summary = master.summary().unwrap();
+        assert_eq!(summary.outputs.len(), 1);
+        let output = &summary.outputs[0];
+        assert_eq!(output.name, "DP-1");
+        assert_eq!(output.controller, Some(10));
+        assert_eq!(output.mode.as_ref().map(| m | m.display), Some((1920, 1080)));
+    }
+
+    #[test]
+    fn summary_tolerates_missing_encoder() {
+        let mut card = MockCard::sample();
+        card.encoders.clear();
+        install(card);
+        let dev = Device::open("/dev/null").unwrap();
+        let master = dev.lock_master().unwrap();
+        let summary = master.summary().unwrap();
+        assert_eq!(summary.outputs.len(), 1);
+        assert_eq!(summary.outputs[0].controller, None);
+    }
+
+    #[test]
+    fn validates_modes_against_connector() {
+        let dev = open();
+        let master = dev.lock_master().unwrap();
+        let connector = master.connector(30).unwrap();
+        assert_eq!(connector.name(), "DP-1");
+
+        // Listed modes and custom modes within the connector's range pass
+        assert!(connector.validate_mode(&connector.modes()[2]).is_ok());
+        assert!(connector.validate_mode(&fixtures::mode(1600, 900, 60, false)).is_ok());
+
+        let err = connector.validate_mode(&fixtures::mode(2560, 1440, 144, false)).unwrap_err();
+        assert_eq!(err.to_string(), "mode 2560x1440@144 not supported by DP-1");
+
+        // Fits the resolution, but needs a faster pixel clock
+        assert!(connector.validate_mode(&fixtures::mode(1920, 1080, 144, false)).is_err());
+    }
+
+    #[test]
+    fn assigns_current_or_idle_controller() {
+        let dev = open();
+        let master = dev.lock_master().unwrap();
+        let dp = master.connector(30).unwrap();
+        assert_eq!(master.assign_controller(&dp).unwrap().id, 10);
+
+        // The eDP panel is not driven, and controller 10 is busy with DP-1
+        let edp = master.connector(31).unwrap();
+        assert_eq!(master.assign_controller(&edp).unwrap().id, 11);
+    }
+
+    #[test]
+    fn does_not_take_over_busy_controllers() {
+        let mut card = MockCard::sample();
+        card.controllers[1].mode = card.controllers[0].mode.clone();
+        install(card);
+        let dev = Device::open("/dev/null").unwrap();
+        let master = dev.lock_master().unwrap();
+        let edp = master.connector(31).unwrap();
+        let err = master.assign_controller(&edp).err().unwrap();
+        assert!(match *err.kind() { ErrorKind::NotAvailable => true, _ => false });
+    }
+
+    #[test]
+    fn reports_current_controller_in_use() {
+        let dev = open();
+        let master = dev.lock_master().unwrap();
+        let _held = master.controller(10).unwrap();
+        let dp = master.connector(30).unwrap();
+        let err = master.assign_controller(&dp).err().unwrap();
+        assert!(match *err.kind() { ErrorKind::NotAvailable => true, _ => false });
+    }
+
+    #[test]
+    fn controller_index_follows_possible_controllers() {
+        let mut card = MockCard::sample();
+        card.encoders[0].possible_controllers = 0b10;
+        install(card);
+        let dev = Device::open("/dev/null").unwrap();
+        let master = dev.lock_master().unwrap();
+
+        let encoder = master.encoder(20).unwrap();
+        let controllers: Vec<_> = encoder.controllers().map(| c | c.unwrap().id).collect();
+        assert_eq!(controllers, vec![11]);
+        assert_eq!(master.controller_index(10), Some(0));
+        assert_eq!(master.controller_index(11), Some(1));
+
+        // Returning a controller must not change the reported order
+        drop(master.controller(10).unwrap());
+        let order: Vec<_> = master.controllers().map(| c | c.unwrap().id).collect();
+        assert_eq!(order, vec![10, 11]);
+    }
+
+    #[test]
+    fn reads_and_requests_privacy_screen() {
+        let dev = open();
+        let master = dev.lock_master().unwrap();
+        assert_eq!(master.connector(30).unwrap().privacy_screen().unwrap(), None);
+
+        let edp = master.connector(31).unwrap();
+        let state = edp.privacy_screen().unwrap().unwrap();
+        assert!(!state.enabled && !state.locked && !state.requested);
+
+        // The mock has no firmware to act on the request, so only the
+        // software state changes
+        edp.set_privacy_screen(true).unwrap();
+        let state = edp.privacy_screen().unwrap().unwrap();
+        assert!(state.requested && !state.enabled);
+
+        assert!(master.connector(30).unwrap().set_privacy_screen(true).is_err());
+    }
+
+    #[test]
+    fn reports_locked_privacy_screen() {
+        let mut card = MockCard::sample();
+        card.connectors[1].properties[3].1 = 3;
+        install(card);
+        let dev = Device::open("/dev/null").unwrap();
+        let master = dev.lock_master().unwrap();
+        let state = master.connector(31).unwrap().privacy_screen().unwrap().unwrap();
+        assert!(state.enabled && state.locked && !state.requested);
+    }
+
+    #[test]
+    fn requests_content_protection() {
+        let dev = open();
+        let master = dev.lock_master().unwrap();
+        let dp = master.connector(30).unwrap();
+        assert_eq!(dp.content_protection().unwrap(), Some(ContentProtection::Undesired));
+        dp.set_content_protection(ContentProtection::Desired).unwrap();
+        assert_eq!(dp.content_protection().unwrap(), Some(ContentProtection::Desired));
+
+        let edp = master.connector(31).unwrap();
+        assert_eq!(edp.content_protection().unwrap(), None);
+        assert!(edp.set_content_protection(ContentProtection::Desired).is_err());
+    }
+
+    #[test]
+    fn reads_and_sets_tv_properties() {
+        let mut card = MockCard::sample();
+        card.properties.push(MockProperty {
+            id: 46,
+            name: "mode".to_string(),
+            values: vec![0, 1],
+            enums: vec![(0, "NTSC".to_string()), (1, "PAL".to_string())]
+        });
+        card.properties.push(MockProperty {
+            id: 47,
+            name: "brightness".to_string(),
+            values: vec![0, 100],
+            enums: Vec::new()
+        });
+        card.connectors.push(MockConnector {
+            id: 32,
+            interface: ConnectorInterface::Composite,
+            interface_id: 1,
+            state: ConnectorState::Connected,
+            encoder: 0,
+            encoders: vec![20],
+            modes: vec![fixtures::mode(720, 480, 60, true)],
+            size: (0, 0),
+            properties: vec![(46, 0), (47, 50)]
+        });
+        install(card);
+        let dev = Device::open("/dev/null").unwrap();
+        let master = dev.lock_master().unwrap();
+        let connector = master.connector(32).unwrap();
+        let tv = connector.tv_properties();
+
+        assert_eq!(tv.mode().unwrap(), Some("NTSC".to_string()));
+        tv.set_mode("PAL").unwrap();
+        assert_eq!(tv.mode().unwrap(), Some("PAL".to_string()));
+        assert!(tv.set_mode("SECAM").is_err());
+
+        assert_eq!(tv.get(TvSetting::Brightness).unwrap(), Some(50));
+        assert_eq!(tv.set(TvSetting::Brightness, 250).unwrap(), 100);
+        assert_eq!(tv.get(TvSetting::Brightness).unwrap(), Some(100));
+
+        assert_eq!(tv.standard().unwrap(), None);
+        assert_eq!(tv.get(TvSetting::Hue).unwrap(), None);
+        assert!(tv.set(TvSetting::Hue, 10).is_err());
+    }
+}
@@ -0,0 +1,336 @@
+//! Display modes and utilities for working with lists of them.
+
+use super::ffi;
+
+use std::cmp::Ordering;
+use std::ffi::CStr;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Mode {
+    pub name: String,
+    pub clock: u32,
+    pub display: (u16, u16),
+    pub hsync: (u16, u16),
+    pub vsync: (u16, u16),
+    pub hskew: u16,
+    pub vscan: u16,
+    pub htotal: u16,
+    pub vtotal: u16,
+    pub vrefresh: u32,
+    pub flags: u32,
+    pub mode_type: u32,
+}
+
+impl Mode {
+    /// Return the refresh rate of the mode in Hz, calculated from the pixel
+    /// clock and total timings. Falls back to the reported `vrefresh` if the
+    /// timings are incomplete.
+    pub fn refresh_rate(&self) -> f64 {
+        if self.htotal == 0 || self.vtotal == 0 {
+            return self.vrefresh as f64;
+        }
+        let mut pixels = self.htotal as f64 * self.vtotal as f64;
+        if self.is_interlaced() {
+            pixels /= 2.0;
+        }
+        if self.is_doublescan() {
+            pixels *= 2.0;
+        }
+        if self.vscan > 1 {
+            pixels *= self.vscan as f64;
+        }
+        self.clock as f64 * 1000.0 / pixels
+    }
+
+    /// Returns true if the timings of the mode are well formed: a non-zero
+    /// clock and size, with the display, sync and total values of each
+    /// direction in increasing order.
+    pub fn has_valid_timings(&self) -> bool {
+        let (hdisplay, vdisplay) = self.display;
+        let (hsync_start, hsync_end) = self.hsync;
+        let (vsync_start, vsync_end) = self.vsync;
+        self.clock > 0 && hdisplay > 0 && vdisplay > 0 &&
+        hdisplay <= hsync_start && hsync_start <= hsync_end && hsync_end <= self.htotal &&
+        vdisplay <= vsync_start && vsync_start <= vsync_end && vsync_end <= self.vtotal
+    }
+
+    /// Returns the decoded video mode flags.
+    pub fn flags(&self) -> ModeFlags {
+        ModeFlags(self.flags)
+    }
+
+    /// Returns the decoded mode type flags.
+    pub fn type_flags(&self) -> ModeTypeFlags {
+        ModeTypeFlags(self.mode_type)
+    }
+
+    /// Returns true if the mode is interlaced.
+    pub fn is_interlaced(&self) -> bool {
+        self.flags & unsafe { ffi::FFI_DRM_MODE_FLAG_INTERLACE } != 0
+    }
+
+    /// Returns true if each line of the mode is scanned out twice.
+    pub fn is_doublescan(&self) -> bool {
+        self.flags & unsafe { ffi::FFI_DRM_MODE_FLAG_DBLSCAN } != 0
+    }
+
+    /// Returns true if the driver marked this as the preferred mode of the
+    /// display.
+    pub fn is_preferred(&self) -> bool {
+        self.mode_type & unsafe { ffi::FFI_DRM_MODE_TYPE_PREFERRED } != 0
+    }
+
+    /// Returns true if the mode is a stereoscopic 3D mode.
+    pub fn is_stereo(&self) -> bool {
+        self.flags & unsafe { ffi::FFI_DRM_MODE_FLAG_3D_MASK } != 0
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+/// A set of criteria used to narrow down a list of `Mode` objects. The default
+/// filter accepts every mode.
+pub struct ModeFilter {
+    /// Reject interlaced modes.
+    pub exclude_interlaced: bool,
+    /// Reject doublescan modes.
+    pub exclude_doublescan: bool,
+    /// Reject stereoscopic 3D modes.
+    pub exclude_stereo: bool,
+    /// Reject modes with a refresh rate above this value, in Hz.
+    pub max_refresh: Option<f64>
+}
+
+impl ModeFilter {
+    /// Returns true if the given `Mode` passes the filter.
+    pub fn accepts(&self, mode: &Mode) -> bool {
+        if self.exclude_interlaced && mode.is_interlaced() {
+            return false;
+        }
+        if self.exclude_doublescan && mode.is_doublescan() {
+            return false;
+        }
+        if self.exclude_stereo && mode.is_stereo() {
+            return false;
+        }
+        match self.max_refresh {
+            Some(max) => mode.refresh_rate() <= max,
+            None => true
+        }
+    }
+}
+
+impl From<ffi::drm_mode_modeinfo> for Mode {
+    fn from(raw: ffi::drm_mode_modeinfo) -> Mode {
+        let name = unsafe {
+            CStr::from_ptr(raw.name.as_ptr()).to_str().unwrap()
+        };
+
+        Mode {
+            name: name.to_string(),
+            clock: raw.clock,
+            display: (raw.hdisplay, raw.vdisplay),
+            hsync: (raw.hsync_start, raw.hsync_end),
+            vsync: (raw.vsync_start, raw.vsync_end),
+            hskew: raw.hskew,
+            vscan: raw.vscan,
+            htotal: raw.htotal,
+            vtotal: raw.vtotal,
+            vrefresh: raw.vrefresh,
+            flags: raw.flags,
+            mode_type: raw.type_
+        }
+    }
+}
+
+impl Into<ffi::drm_mode_modeinfo> for Mode {
+    fn into(self) -> ffi::drm_mode_modeinfo {
+        let (hdisplay, vdisplay) = self.display;
+        let (hsync_start, hsync_end) = self.hsync;
+        let (vsync_start, vsync_end) = self.vsync;
+
+        ffi::drm_mode_modeinfo {
+            name: [0; 32],
+            clock: self.clock,
+            hdisplay: hdisplay,
+            vdisplay: vdisplay,
+            hsync_start: hsync_start,
+            hsync_end: hsync_end,
+            vsync_start: vsync_start,
+            vsync_end: vsync_end,
+            hskew: self.hskew,
+            vscan: self.vscan,
+            htotal: self.htotal,
+            vtotal: self.vtotal,
+            vrefresh: self.vrefresh,
+            flags: self.flags,
+            type_: self.mode_type
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// The video flags of a `Mode`, describing sync polarity, scan type and clock
+/// handling.
+pub struct ModeFlags(u32);
+
+impl ModeFlags {
+    fn has(&self, flag: u32) -> bool {
+        self.0 & flag != 0
+    }
+
+    /// Returns the raw flag bits.
+    pub fn bits(&self) -> u32 { self.0 }
+    /// Positive horizontal sync.
+    pub fn phsync(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_FLAG_PHSYNC }) }
+    /// Negative horizontal sync.
+    pub fn nhsync(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_FLAG_NHSYNC }) }
+    /// Positive vertical sync.
+    pub fn pvsync(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_FLAG_PVSYNC }) }
+    /// Negative vertical sync.
+    pub fn nvsync(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_FLAG_NVSYNC }) }
+    /// Interlaced scanout.
+    pub fn interlace(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_FLAG_INTERLACE }) }
+    /// Each line is scanned out twice.
+    pub fn dblscan(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_FLAG_DBLSCAN }) }
+    /// Composite sync.
+    pub fn csync(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_FLAG_CSYNC }) }
+    /// Positive composite sync.
+    pub fn pcsync(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_FLAG_PCSYNC }) }
+    /// Negative composite sync.
+    pub fn ncsync(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_FLAG_NCSYNC }) }
+    /// The `hskew` value is valid.
+    pub fn hskew(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_FLAG_HSKEW }) }
+    /// Doubled pixel clock.
+    pub fn dblclk(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_FLAG_DBLCLK }) }
+    /// Halved pixel clock.
+    pub fn clkdiv2(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_FLAG_CLKDIV2 }) }
+    /// Stereoscopic 3D mode of any layout.
+    pub fn stereo(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_FLAG_3D_MASK }) }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// The type flags of a `Mode`, describing where the mode came from.
+pub struct ModeTypeFlags(u32);
+
+impl ModeTypeFlags {
+    fn has(&self, flag: u32) -> bool {
+        self.0 & flag != 0
+    }
+
+    /// Returns the raw flag bits.
+    pub fn bits(&self) -> u32 { self.0 }
+    /// The preferred mode of the display.
+    pub fn preferred(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_TYPE_PREFERRED }) }
+    /// A mode provided by the driver, usually from the display's EDID.
+    pub fn driver(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_TYPE_DRIVER }) }
+    /// A mode defined by the user, such as from the kernel command line.
+    pub fn userdef(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_TYPE_USERDEF }) }
+    /// A fallback mode added when no others are known.
+    pub fn default_mode(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_TYPE_DEFAULT }) }
+    /// A built-in mode. Deprecated by the kernel.
+    pub fn builtin(&self) -> bool { self.has(unsafe { ffi::FFI_DRM_MODE_TYPE_BUILTIN }) }
+}
+
+/// Sort a list of modes from largest to smallest resolution by area, and then
+/// from highest to lowest refresh rate.
+pub fn sort_by_area_then_refresh(modes: &mut [Mode]) {
+    modes.sort_by(| a, b | {
+        let area = | mode: &Mode | mode.display.0 as u32 * mode.display.1 as u32;
+        match area(b).cmp(&area(a)) {
+            Ordering::Equal => {
+                b.refresh_rate().partial_cmp(&a.refresh_rate())
+                    .unwrap_or(Ordering::Equal)
+            },
+            ord => ord
+        }
+    });
+}
+
+/// Sort a list of modes for presentation: the preferred mode first, followed
+/// by the remaining modes in the order of `sort_by_area_then_refresh`.
+pub fn sort_preferred_first(modes: &mut [Mode]) {
+    sort_by_area_then_refresh(modes);
+    modes.sort_by_key(| mode | !mode.is_preferred());
+}
+
+/// Find the mode closest to the requested resolution and, optionally, refresh
+/// rate.
+///
+/// Modes are compared first by the sum of their width and height difference
+/// from the request, and then by the difference in refresh rate if one was
+/// given. Remaining ties are broken by preferring a mode no larger than the
+/// requested resolution, so that content is not scaled up, and then by
+/// preferring the mode the driver marked as preferred.
+pub fn nearest(modes: &[Mode], width: u16, height: u16, refresh: Option<f64>) -> Option<&Mode> {
+    let distance = | mode: &Mode | {
+        let dw = (mode.display.0 as i32 - width as i32).abs() as u32;
+        let dh = (mode.display.1 as i32 - height as i32).abs() as u32;
+        dw + dh
+    };
+    let refresh_distance = | mode: &Mode | match refresh {
+        Some(rate) => (mode.refresh_rate() - rate).abs(),
+        None => 0.0
+    };
+    let scales_up = | mode: &Mode | mode.display.0 > width || mode.display.1 > height;
+
+    modes.iter().min_by(| a, b | {
+        distance(a).cmp(&distance(b))
+            .then_with(|| {
+                refresh_distance(a).partial_cmp(&refresh_distance(b))
+                    .unwrap_or(Ordering::Equal)
+            })
+            .then_with(|| scales_up(a).cmp(&scales_up(b)))
+            .then_with(|| b.is_preferred().cmp(&a.is_preferred()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fixtures::mode;
+
+    #[test]
+    fn preferred_mode_sorts_first() {
+        let mut modes = vec![mode(1920, 1080, 60, false),
+                             mode(1280, 720, 60, true),
+                             mode(2560, 1440, 60, false)];
+        sort_preferred_first(&mut modes);
+        assert_eq!(modes[0].display, (1280, 720));
+        assert_eq!(modes[1].display, (2560, 1440));
+        assert_eq!(modes[2].display, (1920, 1080));
+    }
+
+    #[test]
+    fn equal_area_sorts_by_refresh() {
+        let mut modes = vec![mode(1920, 1080, 50, false),
+                             mode(1280, 720, 60, false),
+                             mode(1920, 1080, 144, false),
+                             mode(1920, 1080, 60, false)];
+        sort_by_area_then_refresh(&mut modes);
+        let order: Vec<_> = modes.iter().map(| m | (m.display, m.vrefresh)).collect();
+        assert_eq!(order, vec![((1920, 1080), 144), ((1920, 1080), 60),
+                               ((1920, 1080), 50), ((1280, 720), 60)]);
+    }
+
+    #[test]
+    fn nearest_avoids_scaling_up() {
+        // Both candidates are 40 pixels away from 1300x740.
+        let modes = vec![mode(1320, 760, 60, false),
+                         mode(1280, 720, 60, false)];
+        let found = nearest(&modes, 1300, 740, None).unwrap();
+        assert_eq!(found.display, (1280, 720));
+    }
+
+    #[test]
+    fn nearest_prefers_preferred_mode() {
+        let modes = vec![mode(1920, 1080, 60, false),
+                         mode(1920, 1080, 60, true),
+                         mode(1920, 1080, 50, false)];
+        assert!(nearest(&modes, 1920, 1080, Some(60.0)).unwrap().is_preferred());
+
+        // Refresh distance takes precedence over the preferred flag.
+        let found = nearest(&modes, 1920, 1080, Some(50.0)).unwrap();
+        assert_eq!(found.vrefresh, 50);
+        assert!(!found.is_preferred());
+    }
+}
@@ -12,6 +12,59 @@ error_chain! {
             description("unavailable resource requested")
             display("attempted to acquire resource")
         }
+        DeviceNotFound(bus_id: String) {
+            description("no device found")
+            display("no device found with bus id {}", bus_id)
+        }
+        UnsupportedMode(mode: String, connector: String) {
+            description("mode not supported by connector")
+            display("mode {} not supported by {}", mode, connector)
+        }
+        InvalidFormat(bpp: u8, depth: u8) {
+            description("unsupported bpp and depth combination")
+            display("unsupported framebuffer format: bpp {} with depth {}", bpp, depth)
+        }
+        Unsupported(capability: &'static str) {
+            description("operation not supported by the device")
+            display("device does not support {}", capability)
+        }
+        ResourceExhausted(op: &'static str, errno: i32) {
+            description("device resources exhausted")
+            display("{} failed: {} (are framebuffers or buffers being leaked?)",
+                    op, errno_name(*errno))
+        }
+        Ioctl(op: &'static str, errno: i32) {
+            description("ioctl failed")
+            display("{} failed: {}", op, errno_name(*errno))
+        }
     }
 }
 
+use libc;
+
+/// Return the symbolic name of an errno value, such as `"EINVAL"`. Values
+/// without a known name are formatted as `"errno N"`.
+pub fn errno_name(errno: i32) -> String {
+    let name = match errno {
+        libc::EPERM => "EPERM",
+        libc::ENOENT => "ENOENT",
+        libc::EINTR => "EINTR",
+        libc::EIO => "EIO",
+        libc::ENXIO => "ENXIO",
+        libc::EBADF => "EBADF",
+        libc::EAGAIN => "EAGAIN",
+        libc::ENOMEM => "ENOMEM",
+        libc::EACCES => "EACCES",
+        libc::EFAULT => "EFAULT",
+        libc::EBUSY => "EBUSY",
+        libc::ENODEV => "ENODEV",
+        libc::EINVAL => "EINVAL",
+        libc::ENOTTY => "ENOTTY",
+        libc::ENOSPC => "ENOSPC",
+        libc::ERANGE => "ERANGE",
+        libc::ENOSYS => "ENOSYS",
+        libc::EOPNOTSUPP => "EOPNOTSUPP",
+        _ => return format!("errno {}", errno)
+    };
+    name.to_string()
+}